@@ -0,0 +1,87 @@
+//! Compares `Vec::iter().find` with `HashMap::get` for the import lookup done by
+//! `TransformVisitor::visit_mut_ident`, which runs once per identifier inside a component.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use swc_core::atoms::Atom;
+use swc_core::common::SyntaxContext;
+use swc_core::ecma::ast::Id;
+
+const IMPORT_COUNTS: [usize; 5] = [1, 5, 10, 20, 50];
+const IDENTIFIERS_PER_PROGRAM: usize = 100;
+
+/// Mirrors the fields of `ImportSpecification` involved in the lookup.
+struct ImportSpecification {
+    symbol_id: Id,
+    local_imported_symbol: Atom,
+}
+
+fn imports(count: usize) -> Vec<ImportSpecification> {
+    (0..count)
+        .map(|index| {
+            let symbol = Atom::from(format!("Import{index}"));
+            ImportSpecification {
+                symbol_id: (symbol.clone(), SyntaxContext::empty()),
+                local_imported_symbol: symbol,
+            }
+        })
+        .collect()
+}
+
+/// Identifiers of a program where every other identifier references an import and the rest are
+/// locals, props and globals.
+fn identifiers(import_count: usize) -> Vec<Id> {
+    (0..IDENTIFIERS_PER_PROGRAM)
+        .map(|index| {
+            let symbol = if index % 2 == 0 {
+                format!("Import{}", (index / 2) % import_count)
+            } else {
+                format!("local{index}")
+            };
+            (Atom::from(symbol), SyntaxContext::empty())
+        })
+        .collect()
+}
+
+fn bench_import_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("import_lookup");
+    for count in IMPORT_COUNTS {
+        let identifiers = identifiers(count);
+
+        let vec_imports = imports(count);
+        group.bench_with_input(
+            BenchmarkId::new("vec_find", count),
+            &identifiers,
+            |b, ids| {
+                b.iter(|| {
+                    black_box(ids)
+                        .iter()
+                        .filter_map(|id| vec_imports.iter().find(|spec| spec.symbol_id == *id))
+                        .map(|spec| spec.local_imported_symbol.len())
+                        .sum::<usize>()
+                })
+            },
+        );
+
+        let map_imports: HashMap<Id, ImportSpecification> = imports(count)
+            .into_iter()
+            .map(|spec| (spec.symbol_id.clone(), spec))
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("hash_map_get", count),
+            &identifiers,
+            |b, ids| {
+                b.iter(|| {
+                    black_box(ids)
+                        .iter()
+                        .filter_map(|id| map_imports.get(id))
+                        .map(|spec| spec.local_imported_symbol.len())
+                        .sum::<usize>()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_import_lookup);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! Compares transforming a large generated API client, which has no component to inject into,
+//! with and without `TransformConfig::prescan` skipping it.
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use react_magnetic_di_swc_plugin::{TransformConfig, TransformVisitor};
+use swc_core::common::sync::Lrc;
+use swc_core::common::{FileName, SourceMap};
+use swc_core::ecma::ast::{EsVersion, Program};
+use swc_core::ecma::visit::VisitMutWith;
+use swc_ecma_parser::{parse_file_as_module, Syntax};
+
+/// Enough endpoints for a fixture of about 2MB.
+const ENDPOINT_COUNT: usize = 14_000;
+
+fn api_client() -> String {
+    let mut source = String::from("import { client } from './client';\n");
+    for index in 0..ENDPOINT_COUNT {
+        source.push_str(&format!(
+            "export const getResource{index} = async (id, params) => {{ const response = await \
+             client.get(`/resources/{index}/${{id}}`, {{ params }}); return response.data; }};\n"
+        ));
+    }
+    source
+}
+
+fn bench_prescan(c: &mut Criterion) {
+    let cm: Lrc<SourceMap> = Default::default();
+    let file = cm.new_source_file(FileName::Anon.into(), api_client());
+    let module = parse_file_as_module(
+        &file,
+        Syntax::Es(Default::default()),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .expect("invalid fixture");
+    let program = Program::Module(module);
+
+    let mut group = c.benchmark_group("prescan");
+    group.sample_size(10);
+    for prescan in [true, false] {
+        let config = TransformConfig {
+            prescan,
+            ..Default::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::new("api_client", prescan),
+            &program,
+            |b, program| {
+                b.iter_batched(
+                    || program.clone(),
+                    |mut program| {
+                        program.visit_mut_with(&mut TransformVisitor::with_config(config.clone()));
+                        black_box(program)
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_prescan);
+criterion_main!(benches);
@@ -0,0 +1,989 @@
+use crate::config::{ConfigError, PartialTransformConfig, ScopeKind, TransformConfig};
+use crate::import_analysis::{ImportKind, ImportSpecification};
+use crate::{
+    compile_patterns, destructured_bindings, dynamic_import_specifications, expr_path,
+    function_scope_kind, is_react_component_class, is_styled_tag, pascal_case_file_stem,
+    renders_jsx, short_hash, starts_with_uppercase, static_prop_name, validate_names,
+    ActiveReplacement, BindingCollector, ImportReferenceFinder, ScopeBindingCollector,
+    TransformVisitor, TransformableScope, CONFIG_COMMENT, NAME_PLACEHOLDER, REACT_WRAPPERS,
+};
+use regex::Regex;
+use std::rc::Rc;
+use swc_core::atoms::Atom;
+use swc_core::common::errors::HANDLER;
+use swc_core::common::{BytePos, Span, Spanned};
+use swc_core::ecma::ast::{
+    ArrowExpr, BreakStmt, CallExpr, Callee, ClassDecl, ClassMethod, ClassProp, Constructor,
+    ContinueStmt, DefaultDecl, DoWhileStmt, ExportDecl, ExportDefaultDecl, Expr, ExprOrSpread,
+    FnDecl, FnExpr, Function, Id, Ident, JSXElementName, JSXMemberExpr, JSXObject, LabeledStmt,
+    MemberExpr, MemberProp, ModuleDecl, ModuleItem, ObjectLit, ObjectPat, Pat, Program, Prop,
+    PropName, Stmt, TsEnumMember, TsModuleDecl, TsType, VarDeclarator,
+};
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// The dependencies injected into one component scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDeps {
+    /// Name of the scope, made up from the file name for anonymous default exports.
+    pub name: Atom,
+    pub span: Span,
+    /// In the order their declarations are emitted.
+    pub dependencies: Vec<Dependency>,
+    pub skip_reason: Option<SkipReason>,
+}
+
+/// An import injected into a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub local: Atom,
+    pub imported: Atom,
+    pub package: Atom,
+}
+
+/// Why a component-like scope was left untransformed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Whether a method such as `[Symbol.iterator]()` renders can't be told statically.
+    ComputedMethodKey,
+    /// The scope's name matches one of the `excludeScopes` patterns.
+    ExcludedScope,
+}
+
+/// Report the dependencies the transform injects into each component of `program`, leaving
+/// `program` itself as is. Comment pragmas and file-specific options aren't available here, use
+/// [`TransformVisitor::analyze`] to configure those. Fails if `options` can't be used, such as an
+/// `excludeScopes` entry that isn't a valid regex.
+pub fn analyze_dependencies(
+    program: &Program,
+    options: &TransformConfig,
+) -> Result<Vec<ComponentDeps>, ConfigError> {
+    TransformVisitor::with_config(options.clone()).analyze(program)
+}
+
+/// The config as overridden by a `/* @di:config { ... } */` comment.
+pub struct ConfigOverride {
+    pub config: TransformConfig,
+    /// Compiled `TransformConfig::exclude_scopes`.
+    pub exclude_scope_patterns: Vec<Regex>,
+}
+
+/// A component scope found by [`DependencyAnalysis`], along with what's injected into it.
+pub struct ScopeAnalysis {
+    pub name: Atom,
+    pub span: Span,
+    /// Set for scopes left untransformed, which have nothing injected.
+    pub skip_reason: Option<SkipReason>,
+    /// In order of first use. Output order must never come from hashing, as transform results
+    /// are cached by content.
+    pub replacements: Vec<ActiveReplacement>,
+    /// Bindings destructured from `await import('...')`, by the span of the statement
+    /// declaring them, in the order they're injected.
+    pub dynamic_imports: Vec<(Span, Vec<ActiveReplacement>)>,
+    /// Set if a config comment overrides the config for the scope.
+    pub config: Option<Rc<ConfigOverride>>,
+}
+
+/// What the transform does to a program, worked out without changing it.
+#[derive(Default)]
+pub struct ProgramAnalysis {
+    /// In the order they're visited.
+    pub scopes: Vec<ScopeAnalysis>,
+    /// Names anonymous default exports are given, by the span of the export.
+    pub default_export_names: Vec<(Span, Atom)>,
+}
+
+impl ProgramAnalysis {
+    pub fn reports(&self) -> Vec<ComponentDeps> {
+        let dependency = |replacement: &ActiveReplacement| Dependency {
+            local: replacement.import.local_imported_symbol.clone(),
+            imported: replacement.import.dependency_imported_symbol.clone(),
+            package: replacement.import.package_name.clone(),
+        };
+        self.scopes
+            .iter()
+            .map(|scope| ComponentDeps {
+                name: scope.name.clone(),
+                span: scope.span,
+                // Dynamic imports are injected into the scope before its own declarations
+                dependencies: scope
+                    .dynamic_imports
+                    .iter()
+                    .flat_map(|(_, replacements)| replacements)
+                    .chain(&scope.replacements)
+                    .map(dependency)
+                    .collect(),
+                skip_reason: scope.skip_reason,
+            })
+            .collect()
+    }
+}
+
+/// A named scope being analyzed. Functions nested in it share its frame rather than getting
+/// their own, so their references are declared at the top of the scope.
+struct ScopeFrame {
+    symbol: Atom,
+    span: Span,
+    /// Whether references are replaced, which they aren't within excluded scopes and arguments
+    /// of `di()`.
+    is_replaceable: bool,
+    replacements: Vec<ActiveReplacement>,
+    dynamic_imports: Vec<(Span, Vec<ActiveReplacement>)>,
+    /// Imports shadowed by a parameter or local declaration of the scope.
+    shadowed_imports: Vec<Id>,
+}
+
+/// Finds the component scopes of a program and the imports referenced within each of them,
+/// for the transform to inject and for [`analyze_dependencies`] to report.
+pub struct DependencyAnalysis<'a> {
+    /// The transform, holding the config and the imports of the file.
+    visitor: &'a TransformVisitor,
+    /// Set while visiting declarations led by a `@di:config` comment.
+    overrides: Option<Rc<ConfigOverride>>,
+    /// The scope being analyzed.
+    scope: Option<ScopeFrame>,
+    /// Name of the function about to be visited, given by the declaration it's bound to.
+    pending_scope_symbol: Option<Atom>,
+    /// Name of the class being visited, whose methods are injected as one component.
+    class_symbol: Option<Atom>,
+    analysis: ProgramAnalysis,
+}
+
+impl<'a> DependencyAnalysis<'a> {
+    /// Analysis for the file `visitor` has collected the imports of.
+    pub fn new(visitor: &'a TransformVisitor) -> Self {
+        Self {
+            visitor,
+            overrides: None,
+            scope: None,
+            pending_scope_symbol: None,
+            class_symbol: None,
+            analysis: ProgramAnalysis::default(),
+        }
+    }
+
+    pub fn analyze(mut self, program: &Program) -> ProgramAnalysis {
+        program.visit_with(&mut self);
+        self.analysis
+    }
+
+    fn config(&self) -> &TransformConfig {
+        match &self.overrides {
+            Some(overrides) => &overrides.config,
+            None => &self.visitor.config,
+        }
+    }
+
+    fn exclude_scope_patterns(&self) -> &[Regex] {
+        match &self.overrides {
+            Some(overrides) => &overrides.exclude_scope_patterns,
+            None => &self.visitor.exclude_scope_patterns,
+        }
+    }
+
+    /// Run `visit` with the config overridden by a `/* @di:config { ... } */` comment leading
+    /// `pos`, if there is one.
+    fn with_config_comment(&mut self, pos: BytePos, visit: impl FnOnce(&mut Self)) {
+        let Some(overrides) = self.config_comment(pos) else {
+            return visit(self);
+        };
+        let previous = self.overrides.replace(Rc::new(overrides));
+        visit(self);
+        self.overrides = previous;
+    }
+
+    /// The config as overridden by a `/* @di:config { ... } */` comment leading `pos`. Invalid
+    /// comments, including options that only apply to the whole file, are reported and
+    /// ignored, so a typo doesn't fail the whole build.
+    fn config_comment(&self, pos: BytePos) -> Option<ConfigOverride> {
+        let comment = self
+            .visitor
+            .comments
+            .as_ref()?
+            .get_leading(pos)?
+            .into_iter()
+            .find(|comment| comment.text.trim_start().starts_with(CONFIG_COMMENT))?;
+        let json = comment.text.trim_start()[CONFIG_COMMENT.len()..].trim();
+        let config = serde_json::from_str::<PartialTransformConfig>(json)
+            .map_err(ConfigError::InvalidJson)
+            .and_then(|overrides| {
+                let config = self.config().clone().merge_with(overrides);
+                validate_names(&config)?;
+                let exclude_scope_patterns = compile_patterns(&config.exclude_scopes)?;
+                Ok(ConfigOverride {
+                    config,
+                    exclude_scope_patterns,
+                })
+            });
+        match config {
+            Ok(config) => Some(config),
+            Err(error) => {
+                HANDLER.with(|handler| {
+                    handler.span_warn(
+                        comment.span,
+                        &format!("react-magnetic-di: invalid `{CONFIG_COMMENT}` comment: {error}"),
+                    )
+                });
+                None
+            }
+        }
+    }
+
+    fn skip(&mut self, name: Atom, span: Span, reason: SkipReason) {
+        self.analysis.scopes.push(ScopeAnalysis {
+            name,
+            span,
+            skip_reason: Some(reason),
+            replacements: vec![],
+            dynamic_imports: vec![],
+            config: None,
+        });
+    }
+
+    /// Scope name of a static class member keyed `key`, e.g. `Table.Skeleton`, if
+    /// `static_components` injects it as a component of its own.
+    fn static_component_symbol(&self, key: &PropName) -> Option<Atom> {
+        if !self.config().static_components || self.scope.is_some() {
+            return None;
+        }
+        let class_symbol = self.class_symbol.as_ref()?;
+        Some(format!("{class_symbol}.{}", key.as_ident()?.sym).into())
+    }
+
+    /// Name of the local binding that replaces references to `import` inside a component.
+    fn binding_symbol(&self, import: &ImportSpecification) -> Atom {
+        let config = self.config();
+        // Namespace members such as `Mui.Button` are bound to `_Mui_Button`
+        let local = &import.local_imported_symbol.replace('.', "_");
+        let mut symbol = match &config.name_template {
+            Some(template) => template.replace(NAME_PLACEHOLDER, local),
+            None => format!("{}{local}", config.prefix),
+        };
+        if config.hash_names {
+            // Separated so `("ab", "c")` and `("a", "bc")` don't hash alike
+            let key = format!("{}#{}", import.package_name, import.local_imported_symbol);
+            symbol = format!("{symbol}_{:04x}", short_hash(&key));
+        }
+        while config.reserved_names.contains(&symbol) {
+            symbol.insert(0, '_');
+        }
+        Atom::new(symbol)
+    }
+
+    fn is_wrapper(&self, callee: &Callee) -> bool {
+        let Some(expr) = callee.as_expr() else {
+            return false;
+        };
+        // Matched by binding rather than name, so aliases such as `forwardRef as fr` work too
+        if let Expr::Ident(ident) = &**expr {
+            if self.visitor.react_wrappers.contains(&ident.to_id()) {
+                return true;
+            }
+        }
+        let Some(path) = expr_path(expr) else {
+            return false;
+        };
+        let react_wrapper = path.strip_prefix("React.");
+        self.config().wrappers.contains(&path)
+            || react_wrapper.is_some_and(|name| REACT_WRAPPERS.contains(&name))
+    }
+
+    /// Whether `arg` of a wrapper call is the component it wraps, a function or another wrapper
+    /// call as in `memo(forwardRef((props, ref) => ...))`.
+    fn is_wrapped_component(&self, arg: &ExprOrSpread) -> bool {
+        if arg.spread.is_some() {
+            return false;
+        }
+        match &*arg.expr {
+            Expr::Arrow(_) | Expr::Fn(_) => true,
+            Expr::Call(call) => {
+                self.is_wrapper(&call.callee)
+                    && call.args.iter().any(|arg| self.is_wrapped_component(arg))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_component_factory(&self, callee: &Callee) -> bool {
+        let Some(path) = callee.as_expr().and_then(|expr| expr_path(expr)) else {
+            return false;
+        };
+        self.config().custom_component_factories.contains(&path)
+    }
+
+    /// The imports shadowed by the parameters and declarations of a function, collected before
+    /// visiting its body. After the resolver has run locals never share an `Id` with an import,
+    /// this keeps programs that weren't resolved from rewriting e.g. `function Card({ theme })`.
+    fn shadowed_imports<N: VisitWith<ScopeBindingCollector>>(&self, function: &N) -> Vec<Id> {
+        let mut collector = ScopeBindingCollector {
+            bindings: vec![],
+            is_entered: false,
+        };
+        function.visit_with(&mut collector);
+        collector
+            .bindings
+            .into_iter()
+            .filter(|binding| {
+                self.visitor
+                    .imports
+                    .iter()
+                    .any(|spec| spec.symbol_id == *binding)
+            })
+            .collect()
+    }
+
+    /// The import a reference to `id` within the current scope is replaced for, if any.
+    fn referenced_import(&self, id: &Id) -> Option<Rc<ImportSpecification>> {
+        // Only set while the body of a scope is visited, so a renamed reference always gets a
+        // matching declaration. Anonymous functions outside a named scope are never touched,
+        // nor are module-level values such as `const icons = [Icon1, Icon2]`.
+        if !self.is_in_replaceable_scope() {
+            return None;
+        }
+        let import = self.visitor.imports_by_id.get(id)?;
+        // Checked here rather than when collecting imports, so `@di:config` comments can set it
+        if self
+            .config()
+            .ignore
+            .iter()
+            .any(|name| *name == *import.local_imported_symbol)
+        {
+            return None;
+        }
+        if self.scope.as_ref()?.shadowed_imports.contains(id) {
+            return None;
+        }
+        Some(import.clone())
+    }
+
+    /// Member `prop` of the namespace import `object` references, as an import of its own named
+    /// e.g. `Mui.Button`.
+    fn namespace_member(&self, object: &Ident, prop: &Atom) -> Option<Rc<ImportSpecification>> {
+        let import = self.referenced_import(&object.to_id())?;
+        if import.kind != ImportKind::Namespace {
+            return None;
+        }
+        let local: Atom = format!("{}.{prop}", import.local_imported_symbol).into();
+        Some(Rc::new(ImportSpecification {
+            symbol_id: (local.clone(), import.symbol_id.1),
+            local_imported_symbol: local,
+            dependency_imported_symbol: prop.clone(),
+            package_name: import.package_name.clone(),
+            is_type_only: false,
+            kind: ImportKind::Named,
+        }))
+    }
+
+    /// Have `import` replaced in the current scope, declared on first use.
+    fn add_replacement(&mut self, import: Rc<ImportSpecification>) {
+        // Closing JSX tags and repeated usages reference the same import again
+        let is_replaced = self.scope.as_ref().is_some_and(|frame| {
+            frame
+                .replacements
+                .iter()
+                .any(|replacement| replacement.import.symbol_id == import.symbol_id)
+        });
+        if is_replaced {
+            return;
+        }
+        let symbol = self.binding_symbol(&import);
+        if let Some(frame) = self.scope.as_mut() {
+            frame
+                .replacements
+                .push(ActiveReplacement { symbol, import });
+        }
+    }
+
+    /// Visit a function nested in the current scope, leaving the imports it shadows alone within
+    /// its body only.
+    fn visit_nested_function<N>(&mut self, function: &N)
+    where
+        N: VisitWith<ScopeBindingCollector> + for<'b> VisitWith<DependencyAnalysis<'b>>,
+    {
+        let shadowed_imports = self.shadowed_imports(function);
+        let Some(frame) = self.scope.as_mut() else {
+            return function.visit_children_with(self);
+        };
+        let len = frame.shadowed_imports.len();
+        frame.shadowed_imports.extend(shadowed_imports);
+        function.visit_children_with(self);
+        if let Some(frame) = self.scope.as_mut() {
+            frame.shadowed_imports.truncate(len);
+        }
+    }
+
+    fn is_in_replaceable_scope(&self) -> bool {
+        self.scope
+            .as_ref()
+            .is_some_and(|frame| frame.is_replaceable)
+    }
+
+    /// Enable or disable replacement in the current scope, returning whether it was enabled.
+    fn set_replaceable(&mut self, is_replaceable: bool) -> bool {
+        self.scope
+            .as_mut()
+            .is_some_and(|frame| std::mem::replace(&mut frame.is_replaceable, is_replaceable))
+    }
+
+    fn analyze_arrow(&mut self, arrow: &ArrowExpr, scope_symbol: Atom) {
+        self.pending_scope_symbol = Some(scope_symbol);
+        self.analyze_scope(arrow);
+        self.pending_scope_symbol = None;
+    }
+
+    /// Analyze the components a declaration named `scope_symbol` is initialized with.
+    fn analyze_init(&mut self, init: &Expr, scope_symbol: Atom) {
+        // `const A = B = () => ...` binds the same component to both names, scope it as `A`
+        let mut component = init.unwrap_parens();
+        while let Expr::Assign(assign) = component {
+            component = assign.right.unwrap_parens();
+        }
+        match component {
+            Expr::Arrow(arrow) => self.analyze_arrow(arrow, scope_symbol),
+            Expr::Call(call) if self.is_wrapper(&call.callee) => {
+                self.analyze_wrapper_call(call, scope_symbol)
+            }
+            Expr::Call(call) if self.is_component_factory(&call.callee) => {
+                self.analyze_factory_call(call, scope_symbol)
+            }
+            // Interpolated functions of `styled.div` templates run as the component renders
+            Expr::TaggedTpl(tagged) if is_styled_tag(&tagged.tag) => {
+                tagged.tag.visit_with(self);
+                for expr in &tagged.tpl.exprs {
+                    match &**expr {
+                        Expr::Arrow(arrow) => self.analyze_arrow(arrow, scope_symbol.clone()),
+                        expr => expr.visit_with(self),
+                    }
+                }
+            }
+            _ => init.visit_with(self),
+        }
+    }
+
+    /// Analyze the first function argument of a wrapper call such as
+    /// `Object.assign(() => ..., statics)`, naming it after the binding the call is assigned to.
+    /// The remaining arguments are evaluated at module level and are left alone.
+    fn analyze_wrapper_call(&mut self, call: &CallExpr, scope_symbol: Atom) {
+        let component = call.args.iter().find(|arg| self.is_wrapped_component(arg));
+        match component.map(|arg| &*arg.expr) {
+            Some(Expr::Arrow(arrow)) => self.analyze_arrow(arrow, scope_symbol),
+            Some(Expr::Call(call)) => self.analyze_wrapper_call(call, scope_symbol),
+            Some(Expr::Fn(fn_expr)) => {
+                self.pending_scope_symbol = Some(scope_symbol);
+                fn_expr.visit_with(self);
+                self.pending_scope_symbol = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Analyze the render methods of the object passed to a custom component factory, e.g.
+    /// `defineComponent({ render() { ... } })`.
+    fn analyze_factory_call(&mut self, call: &CallExpr, scope_symbol: Atom) {
+        let Some(object) = call.args.first().and_then(|arg| arg.expr.as_object()) else {
+            return;
+        };
+        for prop in &object.props {
+            let Some(method) = prop.as_prop().and_then(|prop| prop.as_method()) else {
+                continue;
+            };
+            let Some(key) = static_prop_name(&method.key) else {
+                continue;
+            };
+            if !self.config().factory_methods.iter().any(|name| key == name) {
+                continue;
+            }
+            self.pending_scope_symbol = Some(scope_symbol.clone());
+            method.function.visit_with(self);
+            self.pending_scope_symbol = None;
+        }
+    }
+
+    /// Name for the anonymous default export among `items`, along with its index: after the file
+    /// name or else `DefaultExport`. If anything is injected into it, the transform declares it
+    /// as `export default function Card() {}` or `const Card = () => {}; export default Card;`
+    /// for arrows and wrapped components such as `memo(() => ...)`, to pass it as the scope.
+    fn default_export_name(&self, items: &[ModuleItem]) -> Option<(usize, Atom)> {
+        let index = items.iter().position(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export)) => {
+                matches!(&export.decl, DefaultDecl::Fn(FnExpr { ident: None, .. }))
+            }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => {
+                match export.expr.unwrap_parens() {
+                    Expr::Arrow(_) => true,
+                    Expr::Call(call) => {
+                        self.is_wrapper(&call.callee)
+                            && call.args.iter().any(|arg| self.is_wrapped_component(arg))
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        })?;
+        let mut collector = BindingCollector { bindings: vec![] };
+        items.visit_with(&mut collector);
+        let is_bound = |name: &str| {
+            collector.bindings.iter().any(|(sym, _)| sym == name)
+                || self
+                    .visitor
+                    .imports
+                    .iter()
+                    .any(|import| import.local_imported_symbol == name)
+        };
+        let base_name = self
+            .visitor
+            .filename
+            .as_deref()
+            .map(pascal_case_file_stem)
+            .filter(|name| name.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .unwrap_or_else(|| "DefaultExport".into());
+        let mut name = base_name.clone();
+        let mut suffix = 1;
+        while is_bound(&name) {
+            suffix += 1;
+            name = format!("{base_name}{suffix}");
+        }
+        Some((index, name.into()))
+    }
+
+    /// Analyze the functions of an object literal that's destructured right away, as in
+    /// `const { A, b: B } = { A: () => ..., b() { ... } }`, naming each after its binding.
+    fn analyze_destructured_object(&mut self, object: &ObjectLit, pattern: &ObjectPat) {
+        let bindings = destructured_bindings(pattern);
+        for prop in &object.props {
+            let scope_symbol = prop
+                .as_prop()
+                .and_then(|prop| match &**prop {
+                    Prop::KeyValue(key_value) => static_prop_name(&key_value.key),
+                    Prop::Method(method) => static_prop_name(&method.key),
+                    _ => None,
+                })
+                .and_then(|key| bindings.iter().find(|(name, _)| *name == key))
+                .map(|(_, binding)| binding.clone());
+            let Some(scope_symbol) = scope_symbol else {
+                prop.visit_with(self);
+                continue;
+            };
+            match prop.as_prop().map(|prop| &**prop) {
+                Some(Prop::KeyValue(key_value)) => match &*key_value.value {
+                    Expr::Arrow(arrow) => self.analyze_arrow(arrow, scope_symbol),
+                    value => {
+                        self.pending_scope_symbol = Some(scope_symbol);
+                        value.visit_with(self);
+                        self.pending_scope_symbol = None;
+                    }
+                },
+                Some(Prop::Method(method)) => {
+                    self.pending_scope_symbol = Some(scope_symbol);
+                    method.function.visit_with(self);
+                    self.pending_scope_symbol = None;
+                }
+                _ => prop.visit_with(self),
+            }
+        }
+    }
+
+    /// Analyze a component's body, named after the scope itself or else the declaration
+    /// currently being visited. Scopes without a body are visited as is.
+    fn analyze_scope(&mut self, scope: &impl TransformableScope) {
+        let Some(scope_symbol) = scope
+            .scope_name()
+            .or_else(|| self.pending_scope_symbol.take())
+            .or_else(|| self.class_symbol.clone())
+        else {
+            return;
+        };
+        let is_class_member = self.class_symbol.as_ref() == Some(&scope_symbol);
+        let is_excluded = self
+            .exclude_scope_patterns()
+            .iter()
+            .any(|pattern| pattern.is_match(&scope_symbol));
+        let span = scope.span();
+        if is_excluded {
+            // Functions nested in an excluded scope are excluded along with it
+            self.skip(scope_symbol.clone(), span, SkipReason::ExcludedScope);
+            self.scope = Some(ScopeFrame {
+                symbol: scope_symbol,
+                span,
+                is_replaceable: false,
+                replacements: vec![],
+                dynamic_imports: vec![],
+                shadowed_imports: vec![],
+            });
+            scope.visit_children_with(self);
+            self.scope = None;
+            return;
+        }
+        // Components defined inside a helper such as `createCard()` are scopes of their own
+        if !is_class_member
+            && !self
+                .config()
+                .scopes
+                .contains(&function_scope_kind(&scope_symbol))
+        {
+            return scope.visit_children_with(self);
+        }
+        let shadowed_imports = self.shadowed_imports(scope);
+        // Defaults such as `{ data = useData() }` are evaluated before the body, moving their
+        // calls below the declarations would change the order hooks run in
+        let mut finder = self.import_reference_finder();
+        scope.visit_params_with(&mut finder);
+        warn_uninjectable_references(
+            finder.references,
+            "is referenced in a parameter default and can't be injected",
+        );
+        self.scope = Some(ScopeFrame {
+            symbol: scope_symbol,
+            span,
+            is_replaceable: false,
+            replacements: vec![],
+            dynamic_imports: vec![],
+            shadowed_imports,
+        });
+
+        if let Some(body) = scope.body() {
+            // `this` isn't initialized until `super()` returns, so declarations have to go
+            // after it. References up to that point keep using the import directly.
+            let start = match scope.super_call_index() {
+                Some(super_index) => {
+                    let mut finder = self.import_reference_finder();
+                    body.stmts[..=super_index].visit_with(&mut finder);
+                    warn_uninjectable_references(
+                        finder.references,
+                        "is referenced before `super()` and can't be injected",
+                    );
+                    super_index + 1
+                }
+                None => 0,
+            };
+            self.set_replaceable(true);
+            body.stmts[start..].visit_with(self);
+            self.set_replaceable(false);
+        } else if let Some(expr) = scope.expr_body() {
+            self.set_replaceable(true);
+            expr.visit_with(self);
+            self.set_replaceable(false);
+        } else {
+            scope.visit_children_with(self);
+            self.scope = None;
+            return;
+        }
+
+        let frame = self.scope.take().expect("no scope to analyze");
+        self.analysis.scopes.push(ScopeAnalysis {
+            name: frame.symbol,
+            span: frame.span,
+            skip_reason: None,
+            replacements: frame.replacements,
+            dynamic_imports: frame.dynamic_imports,
+            config: self.overrides.clone(),
+        });
+    }
+
+    fn import_reference_finder(&self) -> ImportReferenceFinder<'_> {
+        ImportReferenceFinder {
+            imports: &self.visitor.imports,
+            references: vec![],
+        }
+    }
+
+    /// Treat bindings destructured from `await import('...')` as injectable from the next
+    /// statement on: the declaration goes right after the `await` and later statements of the
+    /// same block reference the injected binding.
+    fn analyze_dynamic_imports(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            let imports = dynamic_import_specifications(stmt);
+            if imports.is_empty() {
+                continue;
+            }
+            let replacements = imports
+                .into_iter()
+                .map(|import| ActiveReplacement {
+                    symbol: self.binding_symbol(&import),
+                    import: Rc::new(import),
+                })
+                .collect();
+            if let Some(frame) = self.scope.as_mut() {
+                frame.dynamic_imports.push((stmt.span(), replacements));
+            }
+        }
+    }
+}
+
+/// Warn about imports referenced where they can't be injected.
+fn warn_uninjectable_references(references: Vec<Ident>, reason: &str) {
+    for reference in references {
+        HANDLER.with(|handler| {
+            handler.span_warn(
+                reference.span,
+                &format!("react-magnetic-di: `{}` {reason}", reference.sym),
+            )
+        });
+    }
+}
+
+impl Visit for DependencyAnalysis<'_> {
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        if self.scope.is_some()
+            || !self.config().scopes.contains(&ScopeKind::Class)
+            || !starts_with_uppercase(&node.ident.sym)
+            || self.config().require_react_component_base && !is_react_component_class(&node.class)
+        {
+            return node.visit_children_with(self);
+        }
+        let class_symbol = self.class_symbol.replace(node.ident.sym.clone());
+        node.visit_children_with(self);
+        self.class_symbol = class_symbol;
+    }
+
+    fn visit_class_method(&mut self, node: &ClassMethod) {
+        // Methods with computed keys such as `[Symbol.iterator]()` are skipped, whether they
+        // render can't be told statically. String keys as in `"render"() {}` are injected like
+        // identifier keys, the class still names the scope.
+        if node.key.is_computed() && self.scope.is_none() {
+            if let Some(scope_symbol) = self.class_symbol.clone() {
+                self.skip(scope_symbol, node.span, SkipReason::ComputedMethodKey);
+            }
+            return;
+        }
+        if self.scope.is_some() || self.class_symbol.is_none() {
+            return node.visit_children_with(self);
+        }
+        // Named after the class, unless it's a static component of its own
+        self.pending_scope_symbol = self
+            .static_component_symbol(&node.key)
+            .filter(|_| node.is_static && renders_jsx(&node.function));
+        self.analyze_scope(node);
+        self.pending_scope_symbol = None;
+    }
+
+    fn visit_class_prop(&mut self, node: &ClassProp) {
+        // Fields such as `renderHeader = () => ...` are methods bound to the instance
+        let Some(class_symbol) = self.class_symbol.clone().filter(|_| self.scope.is_none()) else {
+            return node.visit_children_with(self);
+        };
+        let Some(Expr::Arrow(arrow)) = node.value.as_deref() else {
+            return node.visit_children_with(self);
+        };
+        let static_symbol = self
+            .static_component_symbol(&node.key)
+            .filter(|_| node.is_static && renders_jsx(arrow));
+        self.analyze_arrow(arrow, static_symbol.unwrap_or(class_symbol));
+    }
+
+    fn visit_constructor(&mut self, node: &Constructor) {
+        if self.scope.is_some() {
+            return self.visit_nested_function(node);
+        }
+        self.analyze_scope(node);
+    }
+
+    fn visit_export_default_decl(&mut self, node: &ExportDefaultDecl) {
+        // `export default function Name() {}` is a function expression rather than a `FnDecl`
+        match &node.decl {
+            DefaultDecl::Fn(function) if function.ident.is_some() => self.analyze_scope(function),
+            _ => node.visit_children_with(self),
+        }
+    }
+
+    fn visit_module_items(&mut self, node: &[ModuleItem]) {
+        let Some((index, name)) = self.default_export_name(node) else {
+            return node.visit_children_with(self);
+        };
+        for (position, item) in node.iter().enumerate() {
+            if position != index {
+                item.visit_with(self);
+                continue;
+            }
+            // Analyzed as the declaration the transform names it with
+            let first_scope = self.analysis.scopes.len();
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                    decl: DefaultDecl::Fn(function),
+                    ..
+                })) => {
+                    self.pending_scope_symbol = Some(name.clone());
+                    self.analyze_scope(function);
+                    self.pending_scope_symbol = None;
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => {
+                    self.analyze_init(&export.expr, name.clone())
+                }
+                item => item.visit_with(self),
+            }
+            // Left as it is unless named for the sake of injecting into it
+            let is_injected = self.analysis.scopes[first_scope..]
+                .iter()
+                .any(|scope| !scope.replacements.is_empty() || !scope.dynamic_imports.is_empty());
+            if is_injected {
+                self.analysis
+                    .default_export_names
+                    .push((item.span(), name.clone()));
+            } else {
+                let scopes = self.analysis.scopes.split_off(first_scope);
+                let skipped = scopes
+                    .into_iter()
+                    .filter(|scope| scope.skip_reason.is_some());
+                self.analysis.scopes.extend(skipped);
+            }
+        }
+    }
+
+    fn visit_export_decl(&mut self, node: &ExportDecl) {
+        // A config comment before `export function` is attached to `export` rather than the
+        // function itself
+        if node.decl.is_fn_decl() && self.scope.is_none() {
+            self.with_config_comment(node.span.lo, |analysis| node.visit_children_with(analysis));
+        } else {
+            node.visit_children_with(self);
+        }
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        if self.scope.is_none() {
+            self.with_config_comment(node.function.span.lo, |analysis| {
+                analysis.pending_scope_symbol = Some(node.ident.sym.clone());
+                node.visit_children_with(analysis);
+                analysis.pending_scope_symbol = None;
+            });
+        } else {
+            node.visit_children_with(self);
+        }
+    }
+
+    fn visit_var_declarator(&mut self, node: &VarDeclarator) {
+        if self.scope.is_some() {
+            return node.visit_children_with(self);
+        }
+        if let (Pat::Object(pattern), Some(Expr::Object(object))) =
+            (&node.name, node.init.as_deref())
+        {
+            return self.analyze_destructured_object(object, pattern);
+        }
+        let (Some(ident), Some(init)) = (node.name.as_ident(), &node.init) else {
+            return node.visit_children_with(self);
+        };
+        self.analyze_init(init, ident.sym.clone());
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        // Functions nested inside a component (render props, callbacks, helpers) share the
+        // component's replacements, so they must not inject on their own.
+        if self.scope.is_some() {
+            return self.visit_nested_function(node);
+        }
+        self.analyze_scope(node);
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        self.visit_nested_function(node);
+    }
+
+    fn visit_stmts(&mut self, node: &[Stmt]) {
+        node.visit_children_with(self);
+        if self.config().dynamic_imports && self.is_in_replaceable_scope() {
+            self.analyze_dynamic_imports(node);
+        }
+    }
+
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        if !self.visitor.is_registration(node) {
+            return node.visit_children_with(self);
+        }
+        // Arguments of `di()` and `injectable()` are the keys dependencies are looked up by at
+        // runtime, so they have to stay the original imports
+        let was_replaceable = self.set_replaceable(false);
+        node.visit_children_with(self);
+        self.set_replaceable(was_replaceable);
+    }
+
+    fn visit_ts_module_decl(&mut self, node: &TsModuleDecl) {
+        // `declare namespace` only describes types, there's no code to inject into
+        if node.declare {
+            return;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_labeled_stmt(&mut self, node: &LabeledStmt) {
+        // Labels live in a namespace of their own, `retry:` is no reference to an import `retry`
+        node.body.visit_with(self);
+    }
+
+    fn visit_do_while_stmt(&mut self, node: &DoWhileStmt) {
+        // The body comes first in the source, keep replacements in the order of first use
+        node.body.visit_with(self);
+        node.test.visit_with(self);
+    }
+
+    fn visit_break_stmt(&mut self, _: &BreakStmt) {}
+
+    fn visit_continue_stmt(&mut self, _: &ContinueStmt) {}
+
+    fn visit_ts_type(&mut self, _: &TsType) {
+        // Types are erased, `useRef<Modal>()` or `typeof Modal` never reference the value at
+        // runtime, even if the import isn't type-only
+    }
+
+    fn visit_ts_enum_member(&mut self, node: &TsEnumMember) {
+        // Initializers can reference imports as well as the enum's own members, which shadow
+        // imports of the same name there, as `Small` does in `Large = Small * 2`. `const enum`
+        // initializers also have to stay constant for TypeScript to inline them, so imports
+        // referenced in here keep using the import directly.
+        let was_replaceable = self.set_replaceable(false);
+        node.visit_children_with(self);
+        self.set_replaceable(was_replaceable);
+    }
+
+    fn visit_expr(&mut self, node: &Expr) {
+        // Members of namespace imports are injected one by one, `Mui.Button` as `_Mui_Button`,
+        // so they can be mocked like named imports. Deeper paths inject their first member,
+        // `Mui.Icons.Add` becomes `_Mui_Icons.Add`. Other uses of the namespace, e.g.
+        // `Object.keys(Mui)` or `Mui[name]`, inject the namespace as a whole.
+        if let Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) = node
+        {
+            if let Some(member) = obj
+                .as_ident()
+                .and_then(|object| self.namespace_member(object, &prop.sym))
+            {
+                return self.add_replacement(member);
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_jsx_element_name(&mut self, node: &JSXElementName) {
+        if let JSXElementName::JSXMemberExpr(JSXMemberExpr {
+            obj: JSXObject::Ident(object),
+            prop,
+            ..
+        }) = node
+        {
+            if let Some(member) = self.namespace_member(object, &prop.sym) {
+                return self.add_replacement(member);
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_jsx_object(&mut self, node: &JSXObject) {
+        // The object of a deeper tag such as `<Mui.Icons.Add />`
+        if let JSXObject::JSXMemberExpr(member) = node {
+            if let JSXObject::Ident(object) = &member.obj {
+                if let Some(import) = self.namespace_member(object, &member.prop.sym) {
+                    return self.add_replacement(import);
+                }
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_ident(&mut self, node: &Ident) {
+        if let Some(import) = self.referenced_import(&node.to_id()) {
+            self.add_replacement(import);
+        }
+    }
+}
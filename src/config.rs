@@ -0,0 +1,322 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use swc_core::plugin::proxies::TransformPluginProgramMetadata;
+
+/// Plugin options, passed as the JSON object next to the plugin name in the swc config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransformConfig {
+    /// Suffix generated bindings with a short hash of the import's package and local name, e.g.
+    /// `_Modal_a1b2`, so they can't collide across files once bundled together.
+    pub hash_names: bool,
+    /// Guard injected declarations with a check that `_di` is actually a function, to surface
+    /// misconfigured development builds with a clear error instead of a `TypeError`.
+    pub emit_runtime_check: bool,
+    /// Calls that wrap a component without changing its identity, e.g. `Object.assign` or
+    /// `observer`. For `const X = wrapper(() => ...)` the first function argument is injected as
+    /// `X`, looking through nested wrappers as in `wrapper(memo(() => ...))`.
+    pub wrappers: Vec<String>,
+    /// Adapter functions such as `defineComponent` that take an object whose `factory_methods`
+    /// render the component, e.g. `const X = defineComponent({ render() { ... } })`.
+    pub custom_component_factories: Vec<String>,
+    /// Methods of the object passed to a custom component factory that are injected.
+    pub factory_methods: Vec<String>,
+    /// Only inject imports from these packages or their subpaths, e.g. `["@corp/ui"]`. Imports
+    /// from any package are injected if unset.
+    pub packages: Option<Vec<String>>,
+    /// Local names of imports that are never injected, e.g. `["React", "classnames"]`.
+    pub ignore: Vec<String>,
+    /// Only inject imports whose local name starts with an uppercase letter, i.e. components and
+    /// classes, leaving hooks and utilities alone.
+    pub capitalized_only: bool,
+    /// Only transform files that import `di` or `injectable` from `react-magnetic-di`.
+    pub require_di_import: bool,
+    /// Inject bindings destructured from `await import('...')` inside components, from the
+    /// statement following the `await` on.
+    pub dynamic_imports: bool,
+    /// Leave files without JSX, `createElement`, tagged templates, hooks or declarations named in
+    /// `PascalCase` untouched without transforming them, e.g. large generated API clients. Files
+    /// are always transformed if `scopes` includes plain functions.
+    pub prescan: bool,
+    /// Leave files that already call `di` from `react-magnetic-di` by hand untouched.
+    pub skip_manually_configured: bool,
+    /// Never inject `@emotion/react` imports in files with a `@jsxImportSource @emotion/react`
+    /// pragma. The pragma is read from the file itself, so this works regardless of the
+    /// `importSource` configured for swc's React transform.
+    pub emotion_compat: bool,
+    /// Inject a different import in place of another, keyed by `"source#export"`. With
+    /// `{ "./client#client": "./api#apiClient" }` components using `client` get
+    /// `_di([apiClient], ...)`, and `apiClient` is imported if the file doesn't already.
+    pub redirects: BTreeMap<String, String>,
+    /// How the `_di` declarations of a scope are split, see [`GroupBy`].
+    pub group_by: GroupBy,
+    /// Prepended to an import's name to name the binding that replaces it, e.g. `_Modal`.
+    pub prefix: String,
+    /// Names generated bindings after a template instead of `prefix`, with `{name}` replaced by
+    /// the import's local name, e.g. `"{name}Di"` yields `ModalDi` and `useQueryDi`.
+    pub name_template: Option<String>,
+    /// Names generated bindings must not take, e.g. globals of the target environment. A
+    /// reserved name is prefixed with further underscores until it's free, e.g. `__Modal`.
+    pub reserved_names: Vec<String>,
+    /// Name of the injection helper the generated declarations call, imported as `di` from
+    /// `react-magnetic-di` where needed. Files that already import `di` call it by their own
+    /// local name instead.
+    pub fn_name: String,
+    /// Bind `fn_name` even if the file already imports `di` under another name, by adding an
+    /// alias to that import.
+    pub force_fn_name: bool,
+    /// ESLint rules disabled for each injected statement by a `// eslint-disable-next-line`
+    /// comment, e.g. `["no-underscore-dangle"]`. Requires the host to pass comments through.
+    pub suppress_lint: Vec<String>,
+    /// Attribute relative imports of modules under a path to a package, for directories that
+    /// re-export one, e.g. `{ "src/ui": "@corp/design-system" }`. Paths are relative to the
+    /// project root and imports are resolved against the transformed file's directory.
+    pub package_for_path: BTreeMap<String, String>,
+    /// Only treat classes extending `Component` or `PureComponent`, optionally as members of
+    /// `React`, as components, leaving other classes in component files alone.
+    pub require_react_component_base: bool,
+    /// Inject into static members of classes that render JSX, e.g. `static Skeleton = () =>
+    /// <Shimmer />`, as components of their own scoped by `Table.Skeleton`, rather than as part
+    /// of the class.
+    pub static_components: bool,
+    /// Function called with the scope's name at the top of every injected scope, for runtimes
+    /// that register components, e.g. `"__registerScope"` yields `__registerScope("Card")`.
+    /// Dotted paths such as `"runtime.register"` are supported.
+    pub scope_setup_call: Option<String>,
+    /// Kinds of scopes that are injected, see [`ScopeKind`]. Components and classes by default,
+    /// helpers such as `formatDate` and hooks are left alone unless listed.
+    pub scopes: Vec<ScopeKind>,
+    /// Never inject the default or namespace import of `react` itself, e.g. `React` in
+    /// `React.useState()`. Named imports such as `Suspense` are still injected.
+    pub exclude_react_default: bool,
+    /// Environments the transform runs in, as reported by swc's `envName`, which defaults to
+    /// `NODE_ENV`. Other environments such as `production` are left untouched.
+    pub enabled_envs: Vec<String>,
+    /// Regular expressions for names of scopes that are never injected, e.g. `["^App$",
+    /// "Provider$"]`, where mocking would affect the whole tree.
+    pub exclude_scopes: Vec<String>,
+    /// Shape of the injected code, see [`Mode`]. Unlike `enabledEnvs`, production builds keep
+    /// the renamed bindings, so both builds can share one config.
+    pub mode: Mode,
+}
+
+/// A kind of scope dependencies can be injected into, told apart by React's naming conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScopeKind {
+    /// Functions named in `PascalCase`, including arrows, wrapped and factory components.
+    Component,
+    /// Functions named `use` followed by an uppercase letter, e.g. `useData`.
+    Hook,
+    /// Class declarations named in `PascalCase`, whose methods, arrow fields and constructor are
+    /// injected.
+    Class,
+    /// Any other named function.
+    Function,
+}
+
+/// Overrides for some of the options of [`TransformConfig`], as given for a single component by
+/// a `/* @di:config { "prefix": "__" } */` comment. Options that apply to the whole file, such as
+/// `packages` or `redirects`, can't be overridden.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PartialTransformConfig {
+    pub hash_names: Option<bool>,
+    pub emit_runtime_check: Option<bool>,
+    pub wrappers: Option<Vec<String>>,
+    pub custom_component_factories: Option<Vec<String>>,
+    pub factory_methods: Option<Vec<String>>,
+    pub ignore: Option<Vec<String>>,
+    pub dynamic_imports: Option<bool>,
+    pub group_by: Option<GroupBy>,
+    pub prefix: Option<String>,
+    pub name_template: Option<String>,
+    pub reserved_names: Option<Vec<String>>,
+    pub fn_name: Option<String>,
+    pub force_fn_name: Option<bool>,
+    pub suppress_lint: Option<Vec<String>>,
+    pub require_react_component_base: Option<bool>,
+    pub static_components: Option<bool>,
+    pub scope_setup_call: Option<String>,
+    pub scopes: Option<Vec<ScopeKind>>,
+    pub exclude_scopes: Option<Vec<String>>,
+    pub mode: Option<Mode>,
+}
+
+/// Whether injected dependencies are resolved through `di` or bound as they are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Mode {
+    /// `const [_Modal] = _di([Modal], Card)`, so tests can replace `Modal`.
+    #[default]
+    Development,
+    /// `const [_Modal] = [Modal]`, without importing `di` or calling into it.
+    Production,
+}
+
+/// How injected dependencies are split into `_di` declarations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupBy {
+    /// One declaration per dependency.
+    None,
+    /// One declaration for the whole scope, as emitted by the Babel plugin, e.g.
+    /// `const [_Modal, _useQuery] = _di([Modal, useQuery], Card)`, in order of first use.
+    #[default]
+    Scope,
+    /// One declaration per source package, e.g. `const [_Modal, _Dialog] = _di([Modal, Dialog],
+    /// Card)`, in order of each package's first use.
+    Package,
+}
+
+impl Default for TransformConfig {
+    fn default() -> Self {
+        Self {
+            hash_names: false,
+            emit_runtime_check: false,
+            wrappers: vec!["Object.assign".into(), "Object.freeze".into()],
+            custom_component_factories: vec![],
+            factory_methods: vec!["render".into(), "setup".into()],
+            packages: None,
+            ignore: vec![],
+            capitalized_only: false,
+            require_di_import: false,
+            dynamic_imports: false,
+            prescan: true,
+            skip_manually_configured: false,
+            emotion_compat: false,
+            redirects: BTreeMap::new(),
+            group_by: GroupBy::Scope,
+            prefix: "_".into(),
+            name_template: None,
+            reserved_names: vec![],
+            fn_name: "_di".into(),
+            force_fn_name: false,
+            suppress_lint: vec![],
+            package_for_path: BTreeMap::new(),
+            require_react_component_base: false,
+            static_components: false,
+            scope_setup_call: None,
+            scopes: vec![ScopeKind::Component, ScopeKind::Class],
+            exclude_react_default: true,
+            enabled_envs: vec!["development".into(), "test".into()],
+            exclude_scopes: vec![],
+            mode: Mode::Development,
+        }
+    }
+}
+
+impl TransformConfig {
+    /// Read the plugin options from the host, if any were configured.
+    pub fn from_metadata(
+        metadata: &TransformPluginProgramMetadata,
+    ) -> Result<Option<Self>, ConfigError> {
+        metadata
+            .get_transform_plugin_config()
+            .map(|config| Self::from_json(&config))
+            .transpose()
+    }
+
+    /// Parse the plugin options as given in the swc config.
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(json).map_err(ConfigError::InvalidJson)
+    }
+
+    /// This config with every option set in `overrides` replaced.
+    pub fn merge_with(self, overrides: PartialTransformConfig) -> Self {
+        Self {
+            hash_names: overrides.hash_names.unwrap_or(self.hash_names),
+            emit_runtime_check: overrides
+                .emit_runtime_check
+                .unwrap_or(self.emit_runtime_check),
+            wrappers: overrides.wrappers.unwrap_or(self.wrappers),
+            custom_component_factories: overrides
+                .custom_component_factories
+                .unwrap_or(self.custom_component_factories),
+            factory_methods: overrides.factory_methods.unwrap_or(self.factory_methods),
+            ignore: overrides.ignore.unwrap_or(self.ignore),
+            dynamic_imports: overrides.dynamic_imports.unwrap_or(self.dynamic_imports),
+            group_by: overrides.group_by.unwrap_or(self.group_by),
+            prefix: overrides.prefix.unwrap_or(self.prefix),
+            name_template: overrides.name_template.or(self.name_template),
+            reserved_names: overrides.reserved_names.unwrap_or(self.reserved_names),
+            fn_name: overrides.fn_name.unwrap_or(self.fn_name),
+            force_fn_name: overrides.force_fn_name.unwrap_or(self.force_fn_name),
+            suppress_lint: overrides.suppress_lint.unwrap_or(self.suppress_lint),
+            require_react_component_base: overrides
+                .require_react_component_base
+                .unwrap_or(self.require_react_component_base),
+            static_components: overrides
+                .static_components
+                .unwrap_or(self.static_components),
+            scope_setup_call: overrides.scope_setup_call.or(self.scope_setup_call),
+            scopes: overrides.scopes.unwrap_or(self.scopes),
+            exclude_scopes: overrides.exclude_scopes.unwrap_or(self.exclude_scopes),
+            mode: overrides.mode.unwrap_or(self.mode),
+            ..self
+        }
+    }
+}
+
+/// An option the transform can't work with.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The options don't deserialize as [`TransformConfig`].
+    InvalidJson(serde_json::Error),
+    /// An `excludeScopes` entry isn't a valid regex.
+    InvalidExcludeScope {
+        pattern: String,
+        error: regex::Error,
+    },
+    /// `nameTemplate` lacks `{name}`, yields invalid identifiers or the import's own name.
+    InvalidNameTemplate(String),
+    /// `prefix` is empty or yields invalid identifiers.
+    InvalidPrefix(String),
+    /// `fnName` isn't a valid identifier.
+    InvalidFnName(String),
+    /// A `redirects` target isn't of the form `source#export`.
+    InvalidRedirectTarget(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidJson(error) => write!(f, "{error}"),
+            Self::InvalidExcludeScope { pattern, error } => {
+                write!(f, "invalid excludeScopes pattern `{pattern}`: {error}")
+            }
+            Self::InvalidNameTemplate(template) => write!(
+                f,
+                "invalid nameTemplate `{template}`, it has to include `{{name}}` and yield valid \
+                 identifiers other than the import's own name"
+            ),
+            Self::InvalidPrefix(prefix) => write!(
+                f,
+                "invalid prefix `{prefix}`, it has to be non-empty and yield valid identifiers"
+            ),
+            Self::InvalidFnName(fn_name) => {
+                write!(
+                    f,
+                    "invalid fnName `{fn_name}`, it has to be a valid identifier"
+                )
+            }
+            Self::InvalidRedirectTarget(target) => {
+                write!(
+                    f,
+                    "invalid redirect target `{target}`, expected `source#export`"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidJson(error) => Some(error),
+            Self::InvalidExcludeScope { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
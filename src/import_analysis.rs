@@ -1,31 +1,194 @@
+use std::collections::{BTreeMap, HashSet};
 use swc_core::atoms::Atom;
-use swc_core::ecma::ast::{Id, ImportDecl, ImportSpecifier};
-use swc_core::ecma::visit::Visit;
+use swc_core::ecma::ast::{
+    BindingIdent, ClassDecl, DefaultDecl, ExportDefaultDecl, ExportDefaultExpr, Expr, FnDecl,
+    FnExpr, Id, Ident, IdentName, ImportDecl, ImportSpecifier, JSXElement, JSXFragment, TaggedTpl,
+};
+use swc_core::ecma::visit::{Visit, VisitWith};
 
-#[allow(unused)]
+pub const DI_PACKAGE: &str = "react-magnetic-di";
+
+/// Exports of [`DI_PACKAGE`] that declare injections, `di` in components and `injectable` in
+/// the tests replacing their dependencies.
+pub const REGISTRATION_EXPORTS: [&str; 2] = ["di", "injectable"];
+
+/// A binding introduced by an import declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImportSpecification {
+    /// Local binding, including its syntax context.
     pub symbol_id: Id,
+    /// Local name, `M` in `import { Modal as M } from 'ui'`.
     pub local_imported_symbol: Atom,
+    /// Exported name, `Modal` in `import { Modal as M } from 'ui'`, the local name for default
+    /// and namespace imports.
     pub dependency_imported_symbol: Atom,
+    /// Module specifier, or the package it's attributed to, see
+    /// [`ImportAnalysis::with_package_for_path`].
     pub package_name: Atom,
+    /// Whether it's a `type` specifier. `import type` declarations aren't recorded at all.
     pub is_type_only: bool,
+    pub kind: ImportKind,
+}
+
+/// Which part of a module an import binds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    /// `import { x } from 'pkg'`
+    Named,
+    /// `import x from 'pkg'`
+    Default,
+    /// `import * as x from 'pkg'`
+    Namespace,
 }
 
 /// Traverse module to get all imported symbol `Id` values
+///
+/// ```
+/// use react_magnetic_di_swc_plugin::import_analysis::{ImportAnalysis, ImportKind};
+/// use swc_core::common::{sync::Lrc, FileName, SourceMap};
+/// use swc_core::ecma::visit::VisitWith;
+/// use swc_ecma_parser::{parse_file_as_module, Syntax};
+///
+/// let source_map = SourceMap::default();
+/// let file = source_map.new_source_file(
+///     Lrc::new(FileName::Anon),
+///     "import { Modal as M } from 'ui';".into(),
+/// );
+/// let module =
+///     parse_file_as_module(&file, Syntax::default(), Default::default(), None, &mut vec![])
+///         .unwrap();
+///
+/// let mut analysis = ImportAnalysis::new();
+/// module.visit_with(&mut analysis);
+/// let imports = analysis.into_import_specifications();
+/// assert_eq!(imports[0].local_imported_symbol, "M");
+/// assert_eq!(imports[0].dependency_imported_symbol, "Modal");
+/// assert_eq!(imports[0].kind, ImportKind::Named);
+/// ```
 pub struct ImportAnalysis {
     import_specifications: Vec<ImportSpecification>,
+    /// Bindings already recorded, so a declaration repeated verbatim is only recorded once.
+    seen_symbols: HashSet<Id>,
+    is_di_enabled_file: bool,
+    /// Directory of the importing file, used to resolve relative imports.
+    directory: Option<String>,
+    /// Path prefixes and the package that modules under them re-export.
+    package_for_path: Vec<(String, String)>,
+    /// Occurrences of JSX, `createElement`, tagged templates, `use*` names and declarations named
+    /// in `PascalCase`, counted in the same pass so files without any can be skipped, see
+    /// [`ImportAnalysis::may_render`].
+    render_hint_count: usize,
 }
 
 impl ImportAnalysis {
     pub fn new() -> Self {
         Self {
             import_specifications: vec![],
+            seen_symbols: HashSet::new(),
+            is_di_enabled_file: false,
+            directory: None,
+            package_for_path: vec![],
+            render_hint_count: 0,
         }
     }
 
+    /// Attribute relative imports that resolve to within one of the `package_for_path` prefixes
+    /// to the package they map to, so `import { Button } from '../ui'` counts as the package
+    /// `src/ui` re-exports. Relative imports can only be resolved if `filename` is known.
+    pub fn with_package_for_path(
+        mut self,
+        filename: Option<&str>,
+        package_for_path: &BTreeMap<String, String>,
+    ) -> Self {
+        self.directory = filename.map(|filename| match filename.rfind(['/', '\\']) {
+            Some(index) => filename[..index].to_string(),
+            None => String::new(),
+        });
+        self.package_for_path = package_for_path
+            .iter()
+            .map(|(path, package)| (normalize_path(path), package.clone()))
+            .collect();
+        self
+    }
+
+    /// Whether the module imports one of the [`REGISTRATION_EXPORTS`] from `react-magnetic-di`,
+    /// i.e. explicitly opts into dependency injection.
+    pub fn is_di_enabled_file(&self) -> bool {
+        self.is_di_enabled_file
+    }
+
+    /// Whether the module may have components, classes or hooks in it, including anonymous
+    /// default exports the transform names after the file. Modules without, such as generated
+    /// API clients, have no scope worth injecting into unless plain functions are.
+    pub fn may_render(&self) -> bool {
+        self.render_hint_count > 0
+    }
+
+    /// Imports in order of declaration, each binding once.
     pub fn into_import_specifications(self) -> Vec<ImportSpecification> {
         self.import_specifications
     }
+
+    /// Count a declaration of what may be a component or class.
+    fn count_declaration(&mut self, name: &str) {
+        if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            self.render_hint_count += 1;
+        }
+    }
+
+    fn add_specification(&mut self, specification: ImportSpecification) {
+        if self.seen_symbols.insert(specification.symbol_id.clone()) {
+            self.import_specifications.push(specification);
+        }
+    }
+
+    /// Package an import of `source` is attributed to.
+    fn package_name(&self, source: &Atom) -> Atom {
+        let Some(directory) = &self.directory else {
+            return source.clone();
+        };
+        if !source.starts_with("./") && !source.starts_with("../") {
+            return source.clone();
+        }
+        let path = normalize_path(&format!("{directory}/{source}"));
+        let package = self
+            .package_for_path
+            .iter()
+            .find(|(prefix, _)| is_within(&path, prefix));
+        match package {
+            Some((_, package)) => package.as_str().into(),
+            None => source.clone(),
+        }
+    }
+}
+
+impl Default for ImportAnalysis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `path` with `.` and `..` segments applied and `/` as the only separator.
+fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split(['/', '\\']) {
+        match segment {
+            "." => {}
+            ".." if matches!(segments.last(), Some(last) if !last.is_empty() && *last != "..") => {
+                segments.pop();
+            }
+            "" if !segments.is_empty() => {}
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Whether `path` is `prefix` or inside it. Filenames may be absolute while prefixes are relative
+/// to the project root, so `prefix` may also start at any directory of `path`.
+fn is_within(path: &str, prefix: &str) -> bool {
+    let path = format!("/{path}/");
+    path.contains(&format!("/{}/", prefix.trim_start_matches('/')))
 }
 
 impl Visit for ImportAnalysis {
@@ -34,7 +197,7 @@ impl Visit for ImportAnalysis {
             return;
         }
 
-        let package_name = &node.src.value;
+        let package_name = &self.package_name(&node.src.value);
 
         for specifier in &node.specifiers {
             match specifier {
@@ -48,12 +211,18 @@ impl Visit for ImportAnalysis {
                         .map(|s| s.atom())
                         .cloned()
                         .unwrap_or(named.local.sym.clone());
-                    self.import_specifications.push(ImportSpecification {
+                    if package_name == DI_PACKAGE
+                        && REGISTRATION_EXPORTS.contains(&&*dependency_imported_symbol)
+                    {
+                        self.is_di_enabled_file = true;
+                    }
+                    self.add_specification(ImportSpecification {
                         symbol_id,
                         local_imported_symbol,
                         dependency_imported_symbol,
                         package_name: package_name.clone(),
                         is_type_only: named.is_type_only,
+                        kind: ImportKind::Named,
                     });
                 }
                 // import defaultExport
@@ -61,12 +230,13 @@ impl Visit for ImportAnalysis {
                     let symbol_id = default_import.local.to_id();
                     let local_imported_symbol = default_import.local.sym.clone();
                     let dependency_imported_symbol = local_imported_symbol.clone();
-                    self.import_specifications.push(ImportSpecification {
+                    self.add_specification(ImportSpecification {
                         symbol_id,
                         local_imported_symbol,
                         dependency_imported_symbol,
                         package_name: package_name.clone(),
                         is_type_only: node.type_only,
+                        kind: ImportKind::Default,
                     });
                 }
                 // import *
@@ -74,15 +244,86 @@ impl Visit for ImportAnalysis {
                     let symbol_id = namespace_import.local.to_id();
                     let local_imported_symbol = namespace_import.local.sym.clone();
                     let dependency_imported_symbol = local_imported_symbol.clone();
-                    self.import_specifications.push(ImportSpecification {
+                    self.add_specification(ImportSpecification {
                         symbol_id,
                         local_imported_symbol,
                         dependency_imported_symbol,
                         package_name: package_name.clone(),
                         is_type_only: node.type_only,
+                        kind: ImportKind::Namespace,
                     });
                 }
             }
         }
     }
+
+    fn visit_jsx_element(&mut self, _: &JSXElement) {
+        self.render_hint_count += 1;
+    }
+
+    fn visit_jsx_fragment(&mut self, _: &JSXFragment) {
+        self.render_hint_count += 1;
+    }
+
+    fn visit_tagged_tpl(&mut self, _: &TaggedTpl) {
+        // `styled.div` templates may interpolate components
+        self.render_hint_count += 1;
+    }
+
+    fn visit_export_default_decl(&mut self, node: &ExportDefaultDecl) {
+        // Named after the file, which may well be `Card.jsx`
+        if matches!(&node.decl, DefaultDecl::Fn(FnExpr { ident: None, .. })) {
+            self.render_hint_count += 1;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_export_default_expr(&mut self, node: &ExportDefaultExpr) {
+        if matches!(node.expr.unwrap_parens(), Expr::Arrow(_) | Expr::Call(_)) {
+            self.render_hint_count += 1;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.count_declaration(&node.ident.sym);
+        node.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, node: &FnExpr) {
+        if let Some(ident) = &node.ident {
+            self.count_declaration(&ident.sym);
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.count_declaration(&node.ident.sym);
+        node.visit_children_with(self);
+    }
+
+    fn visit_binding_ident(&mut self, node: &BindingIdent) {
+        self.count_declaration(&node.id.sym);
+        node.visit_children_with(self);
+    }
+
+    fn visit_ident(&mut self, node: &Ident) {
+        if is_render_hint(&node.sym) {
+            self.render_hint_count += 1;
+        }
+    }
+
+    fn visit_ident_name(&mut self, node: &IdentName) {
+        if is_render_hint(&node.sym) {
+            self.render_hint_count += 1;
+        }
+    }
+}
+
+/// Whether `name` is `createElement` or named like a hook.
+fn is_render_hint(name: &str) -> bool {
+    name == "createElement"
+        || name.strip_prefix("use").is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_uppercase())
+        })
 }
@@ -1,14 +1,40 @@
-mod import_analysis;
+mod analysis;
+mod config;
+pub mod import_analysis;
+mod module_syntax;
+mod redirects;
 
-use crate::import_analysis::{ImportAnalysis, ImportSpecification};
+pub use crate::analysis::{analyze_dependencies, ComponentDeps, Dependency, SkipReason};
+use crate::analysis::{DependencyAnalysis, ScopeAnalysis};
+pub use crate::config::{
+    ConfigError, GroupBy, Mode, PartialTransformConfig, ScopeKind, TransformConfig,
+};
+use crate::import_analysis::{
+    ImportAnalysis, ImportKind, ImportSpecification, DI_PACKAGE, REGISTRATION_EXPORTS,
+};
+use crate::module_syntax::{detect_module_syntax, ModuleSyntax};
+use crate::redirects::{resolve_redirects, Redirect};
+use regex::Regex;
+use std::collections::HashMap;
 use std::rc::Rc;
 use swc_core::atoms::Atom;
+use swc_core::common::comments::{Comment, CommentKind, Comments};
+use swc_core::common::errors::HANDLER;
+use swc_core::common::plugin::metadata::TransformPluginMetadataContextKind;
 use swc_core::common::util::take::Take;
-use swc_core::ecma::ast::{ClassDecl, FnDecl, Function, Ident, VarDecl, VarDeclarator};
-use swc_core::ecma::visit::{VisitMutWith, VisitWith};
+use swc_core::common::{Span, Spanned, DUMMY_SP};
+use swc_core::ecma::ast::{
+    ArrayLit, ArrayPat, ArrowExpr, BindingIdent, BlockStmt, BlockStmtOrExpr, BreakStmt, CallExpr,
+    Class, ClassDecl, ClassMethod, Constructor, ContinueStmt, Decl, DefaultDecl, Expr, FnDecl,
+    FnExpr, Function, Id, Ident, IdentName, ImportDecl, ImportNamedSpecifier, ImportSpecifier,
+    JSXElement, JSXElementName, JSXFragment, JSXMemberExpr, JSXObject, LabeledStmt, Lit,
+    MemberExpr, MemberProp, Module, ModuleDecl, ModuleExportName, ModuleItem, ObjectPat,
+    ObjectPatProp, Pat, PropName, ReturnStmt, Script, Stmt, Str, TsEnumMember, TsType,
+};
+use swc_core::ecma::visit::{Visit, VisitMutWith, VisitWith};
 use swc_core::ecma::{
     ast::Program,
-    visit::{as_folder, FoldWith, VisitMut},
+    visit::{Fold, FoldWith, VisitMut},
 };
 use swc_core::plugin::{plugin_transform, proxies::TransformPluginProgramMetadata};
 use swc_core::quote;
@@ -18,220 +44,6665 @@ struct ActiveReplacement {
     symbol: Atom,
 }
 
+/// A scope being injected into, as found by `DependencyAnalysis`.
+struct InjectedScope {
+    symbol: Atom,
+    /// Whether references are replaced, which they aren't within arguments of `di()`.
+    is_replaceable: bool,
+    /// Binding that replaces each import referenced in the scope, by the import's `Id`.
+    renames: HashMap<Id, Atom>,
+    /// Dynamic imports not injected yet, by the span of the statement declaring them.
+    dynamic_imports: Vec<(Span, Vec<ActiveReplacement>)>,
+}
+
 #[derive(Default)]
 pub struct TransformVisitor {
+    config: TransformConfig,
+    filename: Option<String>,
+    env: Option<String>,
+    comments: Option<Rc<dyn Comments>>,
     imports: Vec<Rc<ImportSpecification>>,
-    active_replacements: Vec<ActiveReplacement>,
-    is_in_replaceable_scope: bool,
-    current_scope_symbol: Option<Atom>,
+    imports_by_id: HashMap<Id, Rc<ImportSpecification>>,
+    redirects: Vec<Redirect>,
+    /// Bindings of `di` and `injectable` imported from `react-magnetic-di`.
+    registration_fns: Vec<Id>,
+    /// Bindings of React's own component wrappers imported from `react`, see `REACT_WRAPPERS`.
+    react_wrappers: Vec<Id>,
+    /// Helper names the generated declarations call, one per distinct `fn_name` used.
+    used_fn_names: Vec<String>,
+    /// Local name of `di` if the file already imports it from `react-magnetic-di`.
+    di_import_local: Option<Atom>,
+    /// Compiled `TransformConfig::exclude_scopes`.
+    exclude_scope_patterns: Vec<Regex>,
+    /// Scopes to inject into by their span, in reverse order of appearance as several
+    /// generated ones may share a span.
+    analyzed_scopes: HashMap<Span, Vec<ScopeAnalysis>>,
+    /// Names given to anonymous default exports, by the span of the export.
+    default_export_names: Vec<(Span, Atom)>,
+    /// The scope being injected into.
+    scope: Option<InjectedScope>,
 }
 
-impl VisitMut for TransformVisitor {
-    fn visit_mut_class_decl(&mut self, node: &mut ClassDecl) {
-        self.current_scope_symbol = Some(node.ident.sym.clone());
-        node.visit_mut_children_with(self);
-        self.current_scope_symbol = None;
+impl TransformVisitor {
+    pub fn with_config(config: TransformConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
     }
 
-    fn visit_mut_fn_decl(&mut self, node: &mut FnDecl) {
-        if self.current_scope_symbol.is_none() {
-            self.current_scope_symbol = Some(node.ident.sym.clone());
-            node.visit_mut_children_with(self);
-            self.current_scope_symbol = None;
-        } else {
-            node.visit_mut_children_with(self);
+    /// Path of the file being transformed, as reported by the host.
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Name of the environment the host builds for, such as `production`, see
+    /// `TransformConfig::enabled_envs`.
+    pub fn with_env(mut self, env: impl Into<String>) -> Self {
+        self.env = Some(env.into());
+        self
+    }
+
+    /// Comments of the file being transformed, required for pragmas and annotations.
+    pub fn with_comments(mut self, comments: impl Comments + 'static) -> Self {
+        self.comments = Some(Rc::new(comments));
+        self
+    }
+    /// Report what the transform injects into each component of `program`, leaving `program`
+    /// itself as is. The transform injects from the same analysis, so the report always matches
+    /// its output.
+    pub fn analyze(mut self, program: &Program) -> Result<Vec<ComponentDeps>, ConfigError> {
+        if !self.prepare(program)? {
+            return Ok(vec![]);
         }
+        Ok(DependencyAnalysis::new(&self).analyze(program).reports())
     }
 
-    fn visit_mut_var_declarator(&mut self, node: &mut VarDeclarator) {
-        let Some(init) = &mut node.init else {
-            return node.visit_mut_children_with(self);
+    /// Collect the imports of `program` along with everything else injecting depends on.
+    /// Returns whether the file is transformed at all, or why the config can't be used.
+    fn prepare(&mut self, program: &Program) -> Result<bool, ConfigError> {
+        // Injection is test and development tooling, production builds are left as they are
+        if let Some(env) = &self.env {
+            if !self.config.enabled_envs.contains(env) {
+                return Ok(false);
+            }
+        }
+        // Declaration files only describe types, even an added import would corrupt them
+        if is_declaration_file(program, self.filename.as_deref()) {
+            return Ok(false);
+        }
+        self.exclude_scope_patterns = compile_patterns(&self.config.exclude_scopes)?;
+        validate_names(&self.config)?;
+        let mut import_analysis = ImportAnalysis::new()
+            .with_package_for_path(self.filename.as_deref(), &self.config.package_for_path);
+        program.visit_with(&mut import_analysis);
+        if self.config.require_di_import && !import_analysis.is_di_enabled_file() {
+            return Ok(false);
+        }
+        if self.config.prescan
+            && !import_analysis.may_render()
+            && !self.config.scopes.contains(&ScopeKind::Function)
+        {
+            return Ok(false);
+        }
+        let imports: Vec<_> = import_analysis
+            .into_import_specifications()
+            .into_iter()
+            .map(Rc::new)
+            .collect();
+        if self.config.skip_manually_configured && uses_manual_di(program, &imports) {
+            return Ok(false);
+        }
+        let mut collector = BindingCollector { bindings: vec![] };
+        if !self.config.redirects.is_empty() {
+            program.visit_with(&mut collector);
+        }
+        self.redirects = resolve_redirects(&self.config.redirects, &imports, &collector.bindings)?;
+        self.registration_fns = imports
+            .iter()
+            .filter(|import| {
+                import.package_name == DI_PACKAGE
+                    && REGISTRATION_EXPORTS.contains(&&*import.dependency_imported_symbol)
+            })
+            .map(|import| import.symbol_id.clone())
+            .collect();
+        self.di_import_local = imports
+            .iter()
+            .find(|import| {
+                import.package_name == DI_PACKAGE
+                    && import.kind == ImportKind::Named
+                    && import.dependency_imported_symbol == *"di"
+                    && !import.is_type_only
+            })
+            .map(|import| import.local_imported_symbol.clone());
+        self.react_wrappers = imports
+            .iter()
+            .filter(|import| {
+                import.package_name == *"react"
+                    && REACT_WRAPPERS.contains(&&*import.dependency_imported_symbol)
+            })
+            .map(|import| import.symbol_id.clone())
+            .collect();
+        // The `css` prop helpers of Emotion's JSX runtime are styling utilities, never worth mocking
+        let is_emotion_file = self.config.emotion_compat
+            && self.jsx_import_source(program).as_deref() == Some(EMOTION_PACKAGE);
+        self.imports = imports
+            .into_iter()
+            .filter(|import| self.is_injectable(import))
+            .filter(|import| {
+                !is_emotion_file || !is_package_or_subpath(&import.package_name, EMOTION_PACKAGE)
+            })
+            .collect();
+        // Looked up for every identifier within a component, files importing from barrels can
+        // have a hundred imports or more
+        self.imports_by_id = self
+            .imports
+            .iter()
+            .map(|import| (import.symbol_id.clone(), import.clone()))
+            .collect();
+        Ok(true)
+    }
+
+    /// Package named by a `@jsxImportSource` pragma in the file's leading comments.
+    fn jsx_import_source(&self, program: &Program) -> Option<String> {
+        let comments = self.comments.as_ref()?;
+        let first_item_lo = match program {
+            Program::Module(module) => module.body.first().map(|item| item.span_lo()),
+            Program::Script(script) => script.body.first().map(|stmt| stmt.span_lo()),
         };
-        let Some(ident) = node.name.as_ident() else {
-            return node.visit_mut_children_with(self);
+        [Some(program.span_lo()), first_item_lo]
+            .into_iter()
+            .flatten()
+            .filter_map(|pos| comments.get_leading(pos))
+            .flatten()
+            .find_map(|comment| {
+                let mut words = comment.text.split_whitespace();
+                words.find(|word| *word == "@jsxImportSource")?;
+                words
+                    .next()
+                    .map(|source| source.trim_end_matches("*/").to_string())
+            })
+    }
+
+    /// Whether references to `import` should be replaced at all under the current config.
+    fn is_injectable(&self, import: &ImportSpecification) -> bool {
+        if self.config.exclude_react_default
+            && import.package_name == *"react"
+            && import.kind != ImportKind::Named
+        {
+            return false;
+        }
+        if let Some(packages) = &self.config.packages {
+            let is_allowed = packages
+                .iter()
+                .any(|package| is_package_or_subpath(&import.package_name, package));
+            if !is_allowed {
+                return false;
+            }
+        }
+        !self.config.capitalized_only || starts_with_uppercase(&import.local_imported_symbol)
+    }
+
+    /// Whether `call` calls `di` or `injectable` from `react-magnetic-di`.
+    fn is_registration(&self, call: &CallExpr) -> bool {
+        call.callee
+            .as_expr()
+            .and_then(|callee| callee.as_ident())
+            .is_some_and(|callee| self.registration_fns.contains(&callee.to_id()))
+    }
+
+    /// The binding replacing a reference to `id` within the current scope, if any.
+    fn replacement(&self, id: &Id) -> Option<Atom> {
+        let scope = self.scope.as_ref().filter(|scope| scope.is_replaceable)?;
+        scope.renames.get(id).cloned()
+    }
+
+    /// The binding replacing member `prop` of the namespace import `object`, see
+    /// `DependencyAnalysis::namespace_member`.
+    fn member_replacement(&self, object: &Ident, prop: &Atom) -> Option<Atom> {
+        self.replacement(&(format!("{}.{prop}", object.sym).into(), object.ctxt))
+    }
+
+    /// Enable or disable replacement in the current scope, returning whether it was enabled.
+    fn set_replaceable(&mut self, is_replaceable: bool) -> bool {
+        self.scope
+            .as_mut()
+            .is_some_and(|scope| std::mem::replace(&mut scope.is_replaceable, is_replaceable))
+    }
+
+    /// The analysis of the scope spanning `span`, unless it's nested in a scope already.
+    fn analyzed_scope(&mut self, span: Span) -> Option<ScopeAnalysis> {
+        if self.scope.is_some() {
+            return None;
+        }
+        self.analyzed_scopes.get_mut(&span)?.pop()
+    }
+
+    /// Visit a function nested in the current scope, leaving the imports its parameters and
+    /// declarations shadow alone within its body, including members of shadowed namespaces.
+    fn visit_mut_nested_function<N>(&mut self, function: &mut N)
+    where
+        N: VisitWith<ScopeBindingCollector> + VisitMutWith<Self>,
+    {
+        let Some(scope) = self.scope.as_mut() else {
+            return function.visit_mut_children_with(self);
         };
-        let Some(arrow) = init.as_mut_arrow() else {
-            return node.visit_mut_children_with(self);
+        let mut collector = ScopeBindingCollector {
+            bindings: vec![],
+            is_entered: false,
         };
-        if self.current_scope_symbol.is_some() {
-            return node.visit_mut_children_with(self);
+        function.visit_with(&mut collector);
+        let shadowed: Vec<Id> = scope
+            .renames
+            .keys()
+            .filter(|(sym, ctxt)| {
+                collector
+                    .bindings
+                    .iter()
+                    .any(|binding| binding.1 == *ctxt && sym.split('.').next() == Some(&*binding.0))
+            })
+            .cloned()
+            .collect();
+        let removed: Vec<_> = shadowed
+            .iter()
+            .filter_map(|id| scope.renames.remove_entry(id))
+            .collect();
+        function.visit_mut_children_with(self);
+        if let Some(scope) = self.scope.as_mut() {
+            scope.renames.extend(removed);
         }
+    }
+
+    /// Give the anonymous default export among `items` the name it was analyzed with, to pass
+    /// as its scope: `export default function Card() {}`, or `const Card = () => {};
+    /// export default Card;` for arrows and wrapped components such as `memo(() => ...)`.
+    fn name_default_export(&self, items: &mut Vec<ModuleItem>) {
+        let Some((index, name)) = items.iter().enumerate().find_map(|(index, item)| {
+            let span = item.span();
+            self.default_export_names
+                .iter()
+                .find(|(export_span, _)| *export_span == span)
+                .map(|(_, name)| (index, name))
+        }) else {
+            return;
+        };
+        let ident = Ident::from(name.clone());
 
-        self.current_scope_symbol = Some(ident.sym.clone());
-        arrow.visit_mut_children_with(self);
-        self.current_scope_symbol = None;
+        match &mut items[index] {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export)) => {
+                if let DefaultDecl::Fn(function) = &mut export.decl {
+                    function.ident = Some(ident);
+                }
+            }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => {
+                let mut init = *export.expr.take();
+                while let Expr::Paren(paren) = init {
+                    init = *paren.expr;
+                }
+                let declaration = quote!(
+                    "const $name = $init;" as Stmt,
+                    name = ident.clone(),
+                    init: Expr = init
+                );
+                *export.expr = Expr::Ident(ident);
+                items.insert(index, ModuleItem::Stmt(declaration));
+            }
+            _ => {}
+        }
     }
 
-    fn visit_mut_function(&mut self, node: &mut Function) {
-        let Some(body) = &mut node.body else { return };
-        let Some(current_scope_symbol) = self.current_scope_symbol.clone() else {
+    /// Inject into an arrow component. Expression bodies are turned into a `return` statement for
+    /// the declarations to go before, and back again if nothing was injected.
+    fn inject_into_arrow(&mut self, arrow: &mut ArrowExpr, analysis: ScopeAnalysis) {
+        let is_expression_body = arrow.body.is_expr();
+        if let BlockStmtOrExpr::Expr(expr) = &mut *arrow.body {
+            let expr = expr.take();
+            // Without a position of their own, sharing the expression's would move its leading
+            // comments such as `/*#__PURE__*/` in front of the block
+            *arrow.body = BlockStmtOrExpr::BlockStmt(BlockStmt {
+                span: DUMMY_SP,
+                stmts: vec![Stmt::Return(ReturnStmt {
+                    span: DUMMY_SP,
+                    arg: Some(expr),
+                })],
+                ..Default::default()
+            });
+        }
+        self.inject_into_scope(arrow, analysis);
+        let BlockStmtOrExpr::BlockStmt(body) = &mut *arrow.body else {
+            return;
+        };
+        if let [Stmt::Return(ReturnStmt {
+            arg: Some(expr), ..
+        })] = &mut body.stmts[..]
+        {
+            if is_expression_body {
+                *arrow.body = BlockStmtOrExpr::Expr(expr.take());
+            }
+        }
+    }
+
+    /// Inject the declarations `analysis` found for a component into its body.
+    fn inject_into_scope(&mut self, scope: &mut impl TransformableScope, analysis: ScopeAnalysis) {
+        // `this` isn't initialized until `super()` returns, so declarations go after it
+        let start = scope.super_call_index().map_or(0, |index| index + 1);
+        let Some(body) = scope.body_mut() else {
+            return;
+        };
+        let config = analysis
+            .config
+            .as_ref()
+            .map(|overrides| std::mem::replace(&mut self.config, overrides.config.clone()));
+        let has_dynamic_imports = !analysis.dynamic_imports.is_empty();
+        self.scope = Some(InjectedScope {
+            symbol: analysis.name,
+            is_replaceable: false,
+            renames: analysis
+                .replacements
+                .iter()
+                .map(|replacement| {
+                    (
+                        replacement.import.symbol_id.clone(),
+                        replacement.symbol.clone(),
+                    )
+                })
+                .collect(),
+            dynamic_imports: analysis.dynamic_imports,
+        });
+        self.inject_into_stmts(
+            &mut body.stmts,
+            start,
+            body.span,
+            analysis.replacements,
+            has_dynamic_imports,
+        );
+        self.scope = None;
+        if let Some(config) = config {
+            self.config = config;
+        }
+    }
+
+    /// Visit statements from `start` on with replacement enabled and insert the `_di`
+    /// declarations for `replacements`, referenced within them including inside nested
+    /// functions and arrows, right before them. The declarations are attributed to `body_span`
+    /// in source maps.
+    fn inject_into_stmts(
+        &mut self,
+        stmts: &mut Vec<Stmt>,
+        start: usize,
+        body_span: Span,
+        replacements: Vec<ActiveReplacement>,
+        has_dynamic_imports: bool,
+    ) {
+        let mut transformed = stmts.split_off(start);
+        self.set_replaceable(true);
+        transformed.visit_mut_with(self);
+        self.set_replaceable(false);
+        let scope_symbol = self
+            .scope
+            .as_ref()
+            .expect("no scope to inject into")
+            .symbol
+            .clone();
+        let mut declarations = if has_dynamic_imports || !replacements.is_empty() {
+            self.preamble_statements(&scope_symbol)
+        } else {
+            vec![]
+        };
+        declarations.extend(self.injection_statements(replacements, &scope_symbol));
+        self.suppress_lint(&mut declarations);
+        declarations.visit_mut_with(&mut DummySpanReplacer { span: body_span });
+        stmts.extend(transformed);
+        if !declarations.is_empty() {
+            unshadow_scope_name(stmts, &scope_symbol);
+            stmts.splice(start..start, declarations);
+        }
+    }
+
+    /// Lead each of `stmts` with a comment disabling the `suppress_lint` rules for it.
+    fn suppress_lint(&self, stmts: &mut [Stmt]) {
+        let Some(comments) = &self.comments else {
             return;
         };
+        if self.config.suppress_lint.is_empty() {
+            return;
+        }
+        let text = format!(
+            " eslint-disable-next-line {}",
+            self.config.suppress_lint.join(", ")
+        );
+        for stmt in stmts {
+            // Generated statements have no position of their own to attach the comment to
+            let span = Span::dummy_with_cmt();
+            match stmt {
+                Stmt::Decl(Decl::Var(var)) => var.span = span,
+                Stmt::Expr(expr) => expr.span = span,
+                Stmt::If(if_stmt) => if_stmt.span = span,
+                _ => continue,
+            }
+            comments.add_leading(
+                span.lo,
+                Comment {
+                    kind: CommentKind::Line,
+                    span: DUMMY_SP,
+                    text: text.as_str().into(),
+                },
+            );
+        }
+    }
+
+    /// The binding `_di` receives for `import`, which is the import itself unless redirected.
+    fn injected_dependency(&mut self, import: &ImportSpecification) -> Atom {
+        let redirect = self
+            .redirects
+            .iter_mut()
+            .find(|redirect| redirect.source == import.symbol_id);
+        match redirect {
+            Some(redirect) => {
+                redirect.is_used = true;
+                redirect.target_local.clone()
+            }
+            None => import.local_imported_symbol.clone(),
+        }
+    }
+
+    /// Name the generated declarations call `di` by, the file's own import of it unless
+    /// `force_fn_name` asks for `fn_name`.
+    fn helper_name(&self) -> String {
+        match &self.di_import_local {
+            Some(local) if !self.config.force_fn_name => local.to_string(),
+            _ => self.config.fn_name.clone(),
+        }
+    }
 
-        self.is_in_replaceable_scope = true;
-        body.visit_mut_children_with(self);
-        self.is_in_replaceable_scope = false;
-        let active_replacements = self.active_replacements.take();
+    /// The `_di` declarations for `replacements` inside the component named `scope_symbol`.
+    fn injection_statements(
+        &mut self,
+        replacements: Vec<ActiveReplacement>,
+        scope_symbol: &Atom,
+    ) -> Vec<Stmt> {
+        let fn_name = self.helper_name();
+        if self.config.mode == Mode::Production {
+            return self.identity_statements(replacements);
+        }
+        if !replacements.is_empty() && !self.used_fn_names.contains(&fn_name) {
+            self.used_fn_names.push(fn_name.clone());
+        }
         let mut new_statements = vec![];
-        for replacement in active_replacements {
+        let groups: Vec<Vec<ActiveReplacement>> = match self.config.group_by {
+            GroupBy::None => replacements.into_iter().map(|r| vec![r]).collect(),
+            GroupBy::Scope if replacements.is_empty() => vec![],
+            GroupBy::Scope => vec![replacements],
+            GroupBy::Package => group_by_package(replacements),
+        };
+        for group in groups {
+            let mut bindings = vec![];
+            let mut dependencies = vec![];
+            for replacement in group {
+                let dependency = self.injected_dependency(&replacement.import);
+                bindings.push(Some(Pat::Ident(Ident::from(replacement.symbol).into())));
+                dependencies.push(Some(path_expr(&dependency).into()));
+            }
+            let dependencies: Expr = ArrayLit {
+                span: DUMMY_SP,
+                elems: dependencies,
+            }
+            .into();
+            let call = quote!(
+                "$di($dependencies, $scope)" as Expr,
+                di = fn_name.as_str().into(),
+                dependencies: Expr = dependencies,
+                scope: Expr = path_expr(scope_symbol)
+            );
             new_statements.push(quote!(
-                "const [$binding] = _di([$local_sym], $scope)" as Stmt,
-                binding = replacement.symbol.into(),
-                local_sym = replacement.import.local_imported_symbol.clone().into(),
-                scope = current_scope_symbol.clone().into()
+                "const $bindings = $call" as Stmt,
+                bindings: Pat = ArrayPat {
+                    span: DUMMY_SP,
+                    elems: bindings,
+                    optional: false,
+                    type_ann: None,
+                }
+                .into(),
+                call: Expr = call
             ));
         }
+        new_statements
+    }
 
-        body.stmts = new_statements
-            .into_iter()
-            .chain(body.stmts.iter().cloned())
-            .collect();
+    /// Statements heading a scope that has dependencies injected, once however many `_di`
+    /// declarations it gets: the `scope_setup_call` and the runtime check.
+    fn preamble_statements(&self, scope_symbol: &Atom) -> Vec<Stmt> {
+        if self.config.mode == Mode::Production {
+            return vec![];
+        }
+        let mut statements = vec![];
+        if let Some(setup_call) = self.config.scope_setup_call.as_deref() {
+            statements.push(quote!(
+                "$callee($name)" as Stmt,
+                callee: Expr = path_expr(setup_call),
+                name: Expr = Lit::Str(scope_symbol.clone().into()).into()
+            ));
+        }
+        if self.config.emit_runtime_check {
+            let fn_name = self.helper_name();
+            let message = format!(
+                "react-magnetic-di: `{fn_name}` is not defined, make sure the di helper is in scope"
+            );
+            statements.push(quote!(
+                "if (typeof $di !== 'function') throw new Error($message)" as Stmt,
+                di = fn_name.as_str().into(),
+                message: Expr = Lit::Str(Str {
+                    span: DUMMY_SP,
+                    raw: Some(format!("'{message}'").into()),
+                    value: message.into(),
+                })
+                .into()
+            ));
+        }
+        statements
     }
 
-    fn visit_mut_ident(&mut self, node: &mut Ident) {
-        if !self.is_in_replaceable_scope {
-            return;
+    /// Bind each replacement to the import itself, e.g. `const [_Modal] = [Modal]`, for
+    /// [`Mode::Production`].
+    fn identity_statements(&mut self, replacements: Vec<ActiveReplacement>) -> Vec<Stmt> {
+        if replacements.is_empty() {
+            return vec![];
+        }
+        let mut bindings = vec![];
+        let mut dependencies = vec![];
+        for replacement in replacements {
+            let dependency = self.injected_dependency(&replacement.import);
+            bindings.push(Some(Pat::Ident(Ident::from(replacement.symbol).into())));
+            dependencies.push(Some(path_expr(&dependency).into()));
         }
+        vec![quote!(
+            "const $bindings = $dependencies" as Stmt,
+            bindings: Pat = ArrayPat {
+                span: DUMMY_SP,
+                elems: bindings,
+                optional: false,
+                type_ann: None,
+            }
+            .into(),
+            dependencies: Expr = ArrayLit {
+                span: DUMMY_SP,
+                elems: dependencies,
+            }
+            .into()
+        )]
+    }
 
-        let node_id = node.to_id();
-        let Some(import) = self.imports.iter().find(|spec| spec.symbol_id == node_id) else {
+    /// Insert the declarations of dynamic imports right after the statement awaiting them,
+    /// with later statements of the same block referencing the injected bindings.
+    fn inject_dynamic_imports(&mut self, stmts: &mut Vec<Stmt>) {
+        let Some(scope_symbol) = self.scope.as_ref().map(|scope| scope.symbol.clone()) else {
             return;
         };
+        let mut index = 0;
+        while index < stmts.len() {
+            let span = stmts[index].span();
+            index += 1;
+            let Some(replacements) = self.scope.as_mut().and_then(|scope| {
+                let position = scope
+                    .dynamic_imports
+                    .iter()
+                    .position(|(stmt_span, _)| *stmt_span == span)?;
+                Some(scope.dynamic_imports.remove(position).1)
+            }) else {
+                continue;
+            };
 
-        let new_symbol = format!("_{}", import.local_imported_symbol.to_string());
-        let new_symbol = Atom::new(new_symbol);
-        node.sym = new_symbol.clone();
-        self.active_replacements.push(ActiveReplacement {
-            symbol: new_symbol,
-            import: import.clone(),
-        });
+            let mut renamer = BindingRenamer {
+                renames: replacements
+                    .iter()
+                    .map(|replacement| {
+                        (
+                            replacement.import.symbol_id.clone(),
+                            replacement.symbol.clone(),
+                        )
+                    })
+                    .collect(),
+            };
+            for stmt in &mut stmts[index..] {
+                stmt.visit_mut_with(&mut renamer);
+            }
+
+            let mut new_statements = self.injection_statements(replacements, &scope_symbol);
+            self.suppress_lint(&mut new_statements);
+            let inserted = new_statements.len();
+            stmts.splice(index..index, new_statements);
+            index += inserted;
+        }
     }
+}
 
-    fn visit_mut_program(&mut self, node: &mut Program) {
-        let mut import_analysis = ImportAnalysis::new();
-        node.visit_with(&mut import_analysis);
-        let imports = import_analysis.into_import_specifications();
-        self.imports = imports.into_iter().map(Rc::new).collect();
-        node.visit_mut_children_with(self);
+/// A function-like node whose body a component's `_di` declarations are injected into.
+trait TransformableScope:
+    Spanned + VisitWith<ScopeBindingCollector> + for<'a> VisitWith<DependencyAnalysis<'a>>
+{
+    /// Name the node gives its own scope, if it has one.
+    fn scope_name(&self) -> Option<Atom> {
+        None
+    }
+
+    /// The block declarations are inserted into, if the body is one.
+    fn body(&self) -> Option<&BlockStmt>;
+
+    fn body_mut(&mut self) -> Option<&mut BlockStmt>;
+
+    /// An arrow's expression body, which becomes a block once there's something to inject.
+    fn expr_body(&self) -> Option<&Expr> {
+        None
+    }
+
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder);
+
+    /// Index of a top-level `super()` call that declarations have to follow.
+    fn super_call_index(&self) -> Option<usize> {
+        None
     }
 }
 
-/// An example plugin function with macro support.
-/// `plugin_transform` macro interop pointers into deserialized structs, as well
-/// as returning ptr back to host.
-///
-/// It is possible to opt out from macro by writing transform fn manually
-/// if plugin need to handle low-level ptr directly via
-/// `__transform_plugin_process_impl(
-///     ast_ptr: *const u8, ast_ptr_len: i32,
-///     unresolved_mark: u32, should_enable_comments_proxy: i32) ->
-///     i32 /*  0 for success, fail otherwise.
-///             Note this is only for internal pointer interop result,
-///             not actual transform result */`
-///
-/// This requires manual handling of serialization / deserialization from ptrs.
-/// Refer swc_plugin_macro to see how does it work internally.
-#[plugin_transform]
-pub fn process_transform(program: Program, _metadata: TransformPluginProgramMetadata) -> Program {
-    program.fold_with(&mut as_folder(TransformVisitor::default()))
+impl TransformableScope for Function {
+    fn body(&self) -> Option<&BlockStmt> {
+        self.body.as_ref()
+    }
+
+    fn body_mut(&mut self) -> Option<&mut BlockStmt> {
+        self.body.as_mut()
+    }
+
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder) {
+        self.params.visit_with(finder);
+    }
 }
 
-// An example to test plugin transform.
-// Recommended strategy to test plugin's transform is verify
-// the Visitor's behavior, instead of trying to run `process_transform` with mocks
-// unless explicitly required to do so.
-#[cfg(test)]
-mod test {
-    use super::*;
-    use swc_core::ecma::transforms::testing::test_inline_input_output;
-    use swc_core::ecma::visit::as_folder;
-    use swc_ecma_parser::{EsSyntax, Syntax};
+impl TransformableScope for FnExpr {
+    fn scope_name(&self) -> Option<Atom> {
+        self.ident.as_ref().map(|ident| ident.sym.clone())
+    }
 
-    #[test]
-    fn test_should_work_in_class_components() {
-        test_inline_input_output(
-            Syntax::Es(EsSyntax {
-                jsx: true,
-                ..Default::default()
-            }),
-            |_| as_folder(TransformVisitor::default()),
-            // Input codes
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
+    fn body(&self) -> Option<&BlockStmt> {
+        self.function.body.as_ref()
+    }
 
-class MyComponent extends Component {
-    render() {
-        return <Modal />;
+    fn body_mut(&mut self) -> Option<&mut BlockStmt> {
+        self.function.body.as_mut()
     }
-}"#,
-            // Output codes after transformed with plugin
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
 
-class MyComponent extends Component {
-    render() {
-        const [_Modal] = _di([Modal], MyComponent);
-        return <_Modal />;
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder) {
+        self.function.params.visit_with(finder);
     }
-}"#,
-        );
+}
+
+impl TransformableScope for ClassMethod {
+    fn body(&self) -> Option<&BlockStmt> {
+        self.function.body.as_ref()
     }
 
-    #[test]
-    fn test_should_work_in_function_components() {
-        test_inline_input_output(
-            Syntax::Es(EsSyntax {
-                jsx: true,
-                ..Default::default()
-            }),
-            |_| as_folder(TransformVisitor::default()),
-            // Input codes
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
+    fn body_mut(&mut self) -> Option<&mut BlockStmt> {
+        self.function.body.as_mut()
+    }
 
-function MyComponent() {
-    return <Modal />;
-}"#,
-            // Output codes after transformed with plugin
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder) {
+        self.function.params.visit_with(finder);
+    }
+}
 
-function MyComponent() {
-    const [_Modal] = _di([Modal], MyComponent);
-    return <_Modal />;
-}"#,
-        );
+impl TransformableScope for ArrowExpr {
+    fn body(&self) -> Option<&BlockStmt> {
+        self.body.as_block_stmt()
     }
 
-    #[test]
-    fn test_should_work_in_arrow_function_components() {
-        test_inline_input_output(
-            Syntax::Es(EsSyntax {
-                jsx: true,
-                ..Default::default()
-            }),
-            |_| as_folder(TransformVisitor::default()),
-            // Input codes
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
+    fn body_mut(&mut self) -> Option<&mut BlockStmt> {
+        self.body.as_mut_block_stmt()
+    }
 
-const MyComponent = () => {
-    return <Modal />;
-}"#,
-            // Output codes after transformed with plugin
-            r#"
-import React, { Component } from 'react';
-import Modal from 'modal';
+    fn expr_body(&self) -> Option<&Expr> {
+        self.body.as_expr().map(|expr| &**expr)
+    }
 
-const MyComponent = () => {
-    const [_Modal] = _di([Modal], MyComponent);
-    return <_Modal />;
-}"#,
-        );
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder) {
+        self.params.visit_with(finder);
+    }
+}
+
+impl TransformableScope for Constructor {
+    fn body(&self) -> Option<&BlockStmt> {
+        self.body.as_ref()
+    }
+
+    fn body_mut(&mut self) -> Option<&mut BlockStmt> {
+        self.body.as_mut()
+    }
+
+    fn visit_params_with(&self, finder: &mut ImportReferenceFinder) {
+        self.params.visit_with(finder);
+    }
+
+    fn super_call_index(&self) -> Option<usize> {
+        self.body.as_ref()?.stmts.iter().position(is_super_call)
+    }
+}
+
+/// Collects references to any of `imports`.
+struct ImportReferenceFinder<'a> {
+    imports: &'a [Rc<ImportSpecification>],
+    references: Vec<Ident>,
+}
+
+impl Visit for ImportReferenceFinder<'_> {
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+
+    fn visit_ts_type(&mut self, _: &TsType) {}
+
+    fn visit_binding_ident(&mut self, _: &BindingIdent) {}
+
+    fn visit_ident(&mut self, node: &Ident) {
+        let node_id = node.to_id();
+        if self.imports.iter().any(|spec| spec.symbol_id == node_id) {
+            self.references.push(node.clone());
+        }
+    }
+}
+
+/// Whether `function` returns JSX, from a `return` statement or as its expression body.
+fn renders_jsx<N: VisitWith<JsxReturnFinder>>(function: &N) -> bool {
+    let mut finder = JsxReturnFinder {
+        is_in_return: false,
+        found: false,
+    };
+    function.visit_children_with(&mut finder);
+    finder.found
+}
+
+/// Looks for JSX returned by a function, leaving nested functions alone.
+struct JsxReturnFinder {
+    is_in_return: bool,
+    found: bool,
+}
+
+impl Visit for JsxReturnFinder {
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_block_stmt_or_expr(&mut self, node: &BlockStmtOrExpr) {
+        // An arrow's expression body is what it returns
+        self.is_in_return = node.is_expr();
+        node.visit_children_with(self);
+        self.is_in_return = false;
+    }
+
+    fn visit_return_stmt(&mut self, node: &ReturnStmt) {
+        self.is_in_return = true;
+        node.visit_children_with(self);
+        self.is_in_return = false;
+    }
+
+    fn visit_jsx_element(&mut self, _: &JSXElement) {
+        self.found |= self.is_in_return;
+    }
+
+    fn visit_jsx_fragment(&mut self, _: &JSXFragment) {
+        self.found |= self.is_in_return;
+    }
+}
+
+/// Collects the `Id` of every binding declared within a node, including nested functions.
+struct BindingCollector {
+    bindings: Vec<Id>,
+}
+
+impl Visit for BindingCollector {
+    fn visit_binding_ident(&mut self, node: &BindingIdent) {
+        self.bindings.push(node.id.to_id());
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.bindings.push(node.ident.to_id());
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.bindings.push(node.ident.to_id());
+        node.visit_children_with(self);
+    }
+}
+
+/// Collects the parameters and declarations of a function, but not those of the functions nested
+/// in it, which only shadow within their own body.
+struct ScopeBindingCollector {
+    bindings: Vec<Id>,
+    is_entered: bool,
+}
+
+impl ScopeBindingCollector {
+    fn enter<N: VisitWith<Self>>(&mut self, node: &N) {
+        if !self.is_entered {
+            self.is_entered = true;
+            node.visit_children_with(self);
+        }
+    }
+}
+
+impl Visit for ScopeBindingCollector {
+    fn visit_binding_ident(&mut self, node: &BindingIdent) {
+        self.bindings.push(node.id.to_id());
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.bindings.push(node.ident.to_id());
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.bindings.push(node.ident.to_id());
+        node.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        self.enter(node);
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        self.enter(node);
+    }
+
+    fn visit_constructor(&mut self, node: &Constructor) {
+        self.enter(node);
+    }
+}
+
+/// Gives generated nodes, which `quote!` leaves without a location, the span of the code they're
+/// generated for.
+struct DummySpanReplacer {
+    span: Span,
+}
+
+impl VisitMut for DummySpanReplacer {
+    fn visit_mut_span(&mut self, span: &mut Span) {
+        // Spans reserved for comments, see `suppress_lint`, are kept
+        if span.is_dummy_ignoring_cmt() {
+            *span = self.span;
+        }
+    }
+}
+
+/// Renames references to bindings that only become injectable midway through a block.
+struct BindingRenamer {
+    renames: Vec<(Id, Atom)>,
+}
+
+impl VisitMut for BindingRenamer {
+    fn visit_mut_ident(&mut self, node: &mut Ident) {
+        let node_id = node.to_id();
+        if let Some((_, symbol)) = self.renames.iter().find(|(id, _)| *id == node_id) {
+            node.sym = symbol.clone();
+        }
+    }
+
+    fn visit_mut_fn_expr(&mut self, node: &mut FnExpr) {
+        // The name of a function expression is a binding of its own
+        node.function.visit_mut_with(self);
+    }
+}
+
+/// Rename a binding named `name` declared within `stmts`, so that `name` refers to the scope
+/// they belong to again, as the scope argument of `function C() { const C = ...; }` has to.
+fn unshadow_scope_name(stmts: &mut Vec<Stmt>, name: &Atom) {
+    let mut collector = BindingCollector { bindings: vec![] };
+    stmts.visit_with(&mut collector);
+    let Some(shadowing) = collector.bindings.iter().find(|(sym, _)| sym == name) else {
+        return;
+    };
+    let mut renamed = format!("_{name}");
+    while collector.bindings.iter().any(|(sym, _)| *sym == *renamed) {
+        renamed.insert(0, '_');
+    }
+    stmts.visit_mut_with(&mut BindingRenamer {
+        renames: vec![(shadowing.clone(), renamed.into())],
+    });
+}
+
+/// Bindings declared by `const { A, default: B } = await import('pkg')`.
+fn dynamic_import_specifications(stmt: &Stmt) -> Vec<ImportSpecification> {
+    let Some(var) = stmt.as_decl().and_then(|decl| decl.as_var()) else {
+        return vec![];
+    };
+    let mut specifications = vec![];
+    for declarator in &var.decls {
+        let (Pat::Object(pattern), Some(init)) = (&declarator.name, &declarator.init) else {
+            continue;
+        };
+        let Some(call) = init
+            .as_await_expr()
+            .and_then(|await_expr| await_expr.arg.as_call())
+        else {
+            continue;
+        };
+        let Some(package_name) = call
+            .callee
+            .is_import()
+            .then(|| call.args.first())
+            .flatten()
+            .and_then(|arg| arg.expr.as_lit())
+            .and_then(|lit| match lit {
+                Lit::Str(source) => Some(source.value.clone()),
+                _ => None,
+            })
+        else {
+            continue;
+        };
+
+        for prop in &pattern.props {
+            let (local, imported) = match prop {
+                ObjectPatProp::Assign(assign) => (&assign.key.id, assign.key.sym.clone()),
+                ObjectPatProp::KeyValue(key_value) => {
+                    let (Some(local), Some(key)) =
+                        (key_value.value.as_ident(), key_value.key.as_ident())
+                    else {
+                        continue;
+                    };
+                    (&local.id, key.sym.clone())
+                }
+                ObjectPatProp::Rest(_) => continue,
+            };
+            let kind = if imported == "default" {
+                ImportKind::Default
+            } else {
+                ImportKind::Named
+            };
+            specifications.push(ImportSpecification {
+                symbol_id: local.to_id(),
+                local_imported_symbol: local.sym.clone(),
+                dependency_imported_symbol: imported,
+                package_name: package_name.clone(),
+                is_type_only: false,
+                kind,
+            });
+        }
+    }
+    specifications
+}
+
+impl VisitMut for TransformVisitor {
+    fn visit_mut_module_items(&mut self, node: &mut Vec<ModuleItem>) {
+        self.name_default_export(node);
+        node.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_function(&mut self, node: &mut Function) {
+        match self.analyzed_scope(node.span) {
+            Some(analysis) => self.inject_into_scope(node, analysis),
+            None => self.visit_mut_nested_function(node),
+        }
+    }
+
+    fn visit_mut_arrow_expr(&mut self, node: &mut ArrowExpr) {
+        match self.analyzed_scope(node.span) {
+            Some(analysis) => self.inject_into_arrow(node, analysis),
+            None => self.visit_mut_nested_function(node),
+        }
+    }
+
+    fn visit_mut_class_method(&mut self, node: &mut ClassMethod) {
+        match self.analyzed_scope(node.span) {
+            Some(analysis) => self.inject_into_scope(node, analysis),
+            None => node.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_constructor(&mut self, node: &mut Constructor) {
+        match self.analyzed_scope(node.span) {
+            Some(analysis) => self.inject_into_scope(node, analysis),
+            None => self.visit_mut_nested_function(node),
+        }
+    }
+
+    fn visit_mut_stmts(&mut self, node: &mut Vec<Stmt>) {
+        node.visit_mut_children_with(self);
+        let has_dynamic_imports = self
+            .scope
+            .as_ref()
+            .is_some_and(|scope| !scope.dynamic_imports.is_empty());
+        if has_dynamic_imports {
+            self.inject_dynamic_imports(node);
+        }
+    }
+
+    fn visit_mut_call_expr(&mut self, node: &mut CallExpr) {
+        if !self.is_registration(node) {
+            return node.visit_mut_children_with(self);
+        }
+        // Arguments of `di()` and `injectable()` have to stay the original imports, even where
+        // the import is injected elsewhere in the scope
+        let was_replaceable = self.set_replaceable(false);
+        node.visit_mut_children_with(self);
+        self.set_replaceable(was_replaceable);
+    }
+
+    // Labels, types and enum members are left alone like `DependencyAnalysis` leaves them, even
+    // if an import of the same name is injected elsewhere in the scope
+    fn visit_mut_labeled_stmt(&mut self, node: &mut LabeledStmt) {
+        node.body.visit_mut_with(self);
+    }
+
+    fn visit_mut_break_stmt(&mut self, _: &mut BreakStmt) {}
+
+    fn visit_mut_continue_stmt(&mut self, _: &mut ContinueStmt) {}
+
+    fn visit_mut_ts_type(&mut self, _: &mut TsType) {}
+
+    fn visit_mut_ts_enum_member(&mut self, node: &mut TsEnumMember) {
+        let was_replaceable = self.set_replaceable(false);
+        node.visit_mut_children_with(self);
+        self.set_replaceable(was_replaceable);
+    }
+
+    fn visit_mut_expr(&mut self, node: &mut Expr) {
+        if let Expr::Member(MemberExpr {
+            span,
+            obj,
+            prop: MemberProp::Ident(prop),
+        }) = node
+        {
+            if let Some(symbol) = obj
+                .as_ident()
+                .and_then(|object| self.member_replacement(object, &prop.sym))
+            {
+                *node = Ident::new_no_ctxt(symbol, *span).into();
+                return;
+            }
+        }
+        node.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_jsx_element_name(&mut self, node: &mut JSXElementName) {
+        if let JSXElementName::JSXMemberExpr(JSXMemberExpr {
+            span,
+            obj: JSXObject::Ident(object),
+            prop,
+        }) = node
+        {
+            if let Some(symbol) = self.member_replacement(object, &prop.sym) {
+                *node = JSXElementName::Ident(Ident::new_no_ctxt(symbol, *span));
+                return;
+            }
+        }
+        node.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_jsx_object(&mut self, node: &mut JSXObject) {
+        if let JSXObject::JSXMemberExpr(member) = node {
+            if let JSXObject::Ident(object) = &member.obj {
+                if let Some(symbol) = self.member_replacement(object, &member.prop.sym) {
+                    *node = JSXObject::Ident(Ident::new_no_ctxt(symbol, member.span));
+                    return;
+                }
+            }
+        }
+        node.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_ident(&mut self, node: &mut Ident) {
+        if let Some(symbol) = self.replacement(&node.to_id()) {
+            node.sym = symbol;
+        }
+    }
+
+    fn visit_mut_program(&mut self, node: &mut Program) {
+        match self.prepare(node) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(error) => {
+                HANDLER.with(|handler| handler.err(&format!("react-magnetic-di: {error}")));
+                return;
+            }
+        }
+        let analysis = DependencyAnalysis::new(self).analyze(node);
+        self.default_export_names = analysis.default_export_names;
+        for scope in analysis.scopes.into_iter().rev() {
+            if scope.skip_reason.is_none() {
+                self.analyzed_scopes
+                    .entry(scope.span)
+                    .or_default()
+                    .push(scope);
+            }
+        }
+        node.visit_mut_children_with(self);
+
+        let module_syntax = detect_module_syntax(node, self.filename.as_deref());
+        let mut missing_imports: Vec<ModuleItem> = vec![];
+        for redirect in &self.redirects {
+            if !redirect.is_used || redirect.is_imported {
+                continue;
+            }
+            let import = named_import(
+                module_syntax,
+                &redirect.target_export,
+                &redirect.target_local,
+                &redirect.target_package,
+            );
+            // Several redirects can share a target that's only imported once
+            if !missing_imports.contains(&import) {
+                missing_imports.push(import);
+            }
+        }
+        insert_after_imports(node, missing_imports);
+
+        // Generated declarations reference their helper, which has to be bound to `di`
+        for fn_name in &self.used_fn_names {
+            if self.di_import_local.as_deref() == Some(fn_name.as_str()) {
+                continue;
+            }
+            if !alias_di_import(node, std::slice::from_ref(fn_name)) {
+                let di = named_import(
+                    module_syntax,
+                    &"di".into(),
+                    &fn_name.as_str().into(),
+                    &DI_PACKAGE.into(),
+                );
+                prepend_items(node, vec![di]);
+            }
+        }
+    }
+}
+
+/// Make sure each of `fn_names` is bound to `di`, by aliasing it in an existing import from
+/// `react-magnetic-di`, e.g. `import { di as injectDi, di as _di } from 'react-magnetic-di'`.
+/// Returns whether there was such an import.
+fn alias_di_import(program: &mut Program, fn_names: &[String]) -> bool {
+    let Program::Module(module) = program else {
+        return false;
+    };
+    let di_import = module.body.iter_mut().find_map(|item| {
+        let import = item.as_mut_module_decl()?.as_mut_import()?;
+        let imports_di = import.specifiers.iter().any(|specifier| {
+            matches!(specifier, ImportSpecifier::Named(named) if imported_name(named) == "di")
+        });
+        (import.src.value == DI_PACKAGE && !import.type_only && imports_di).then_some(import)
+    });
+    let Some(di_import) = di_import else {
+        return false;
+    };
+    for fn_name in fn_names {
+        let is_bound = di_import.specifiers.iter().any(|specifier| {
+            matches!(specifier, ImportSpecifier::Named(named) if named.local.sym == *fn_name)
+        });
+        if !is_bound {
+            di_import
+                .specifiers
+                .push(ImportSpecifier::Named(ImportNamedSpecifier {
+                    span: DUMMY_SP,
+                    local: Ident::from(fn_name.as_str()),
+                    imported: Some(ModuleExportName::Ident(Ident::from("di"))),
+                    is_type_only: false,
+                }));
+        }
+    }
+    true
+}
+
+/// Name a named import specifier imports, e.g. `di` for `di as _di`.
+fn imported_name(specifier: &ImportNamedSpecifier) -> &str {
+    match &specifier.imported {
+        Some(imported) => imported.atom(),
+        None => &specifier.local.sym,
+    }
+}
+
+/// `import { imported as local } from 'src'`, or `const local = require('src').imported` for
+/// CommonJS.
+fn named_import(syntax: ModuleSyntax, imported: &Atom, local: &Atom, src: &Atom) -> ModuleItem {
+    if syntax == ModuleSyntax::CommonJs {
+        let require =
+            quote!("require($src)" as Expr, src: Expr = Lit::Str(src.clone().into()).into());
+        return ModuleItem::Stmt(quote!(
+            "const $local = $init" as Stmt,
+            local = local.clone().into(),
+            init: Expr = MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(require),
+                prop: MemberProp::Ident(IdentName::new(imported.clone(), DUMMY_SP)),
+            }
+            .into()
+        ));
+    }
+    let imported = (imported != local).then(|| ModuleExportName::Ident(imported.clone().into()));
+    ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+        span: DUMMY_SP,
+        specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
+            span: DUMMY_SP,
+            local: local.clone().into(),
+            imported,
+            is_type_only: false,
+        })],
+        src: Box::new(src.clone().into()),
+        type_only: false,
+        with: None,
+        phase: Default::default(),
+    }))
+}
+
+/// Add `items` after the last top-level import or `require` declaration of `program`, leaving
+/// existing statements in place.
+fn insert_after_imports(program: &mut Program, items: Vec<ModuleItem>) {
+    let directives = directive_count(program);
+    match program {
+        Program::Module(module) => {
+            let position = module
+                .body
+                .iter()
+                .rposition(|item| match item {
+                    ModuleItem::ModuleDecl(decl) => decl.is_import(),
+                    ModuleItem::Stmt(stmt) => is_require_decl(stmt),
+                })
+                .map_or(directives, |index| index + 1);
+            module.body.splice(position..position, items);
+        }
+        Program::Script(script) => {
+            let position = script
+                .body
+                .iter()
+                .rposition(is_require_decl)
+                .map_or(directives, |index| index + 1);
+            let stmts = items.into_iter().filter_map(|item| item.stmt());
+            script.body.splice(position..position, stmts);
+        }
+    }
+}
+
+/// Add `items` at the top of `program`, after its directives.
+fn prepend_items(program: &mut Program, items: Vec<ModuleItem>) {
+    let position = directive_count(program);
+    match program {
+        Program::Module(module) => {
+            module.body.splice(position..position, items);
+        }
+        Program::Script(script) => {
+            let stmts = items.into_iter().filter_map(|item| item.stmt());
+            script.body.splice(position..position, stmts);
+        }
+    }
+}
+
+/// Number of directives such as `'use client'` heading `program`. They only take effect at the
+/// very top, bundlers would treat a client component as a server one if an import went first.
+fn directive_count(program: &Program) -> usize {
+    let is_directive = |stmt: &Stmt| {
+        stmt.as_expr()
+            .is_some_and(|stmt| matches!(&*stmt.expr, Expr::Lit(Lit::Str(_))))
+    };
+    match program {
+        Program::Module(module) => module
+            .body
+            .iter()
+            .take_while(|item| item.as_stmt().is_some_and(is_directive))
+            .count(),
+        Program::Script(script) => script
+            .body
+            .iter()
+            .take_while(|stmt| is_directive(stmt))
+            .count(),
+    }
+}
+
+/// Whether `stmt` declares bindings from a `require()` call, e.g. `const { a } = require('a')`.
+fn is_require_decl(stmt: &Stmt) -> bool {
+    let Some(var) = stmt.as_decl().and_then(|decl| decl.as_var()) else {
+        return false;
+    };
+    var.decls.iter().any(|declarator| {
+        let mut init = declarator.init.as_deref();
+        while let Some(Expr::Member(member)) = init {
+            init = Some(&member.obj);
+        }
+        let Some(Expr::Call(call)) = init else {
+            return false;
+        };
+        call.callee
+            .as_expr()
+            .and_then(|callee| callee.as_ident())
+            .is_some_and(|callee| callee.sym == *"require")
+    })
+}
+
+/// Whether `program` is a `.d.ts` file, or only consists of ambient declarations.
+fn is_declaration_file(program: &Program, filename: Option<&str>) -> bool {
+    let extensions = [".d.ts", ".d.mts", ".d.cts"];
+    if filename.is_some_and(|filename| extensions.iter().any(|ext| filename.ends_with(ext))) {
+        return true;
+    }
+    let Program::Module(module) = program else {
+        return false;
+    };
+    !module.body.is_empty()
+        && module.body.iter().all(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(_)) => true,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => is_ambient_decl(&export.decl),
+            ModuleItem::Stmt(Stmt::Decl(decl)) => is_ambient_decl(decl),
+            _ => false,
+        })
+}
+
+fn is_ambient_decl(decl: &Decl) -> bool {
+    match decl {
+        Decl::Class(class) => class.declare,
+        Decl::Fn(function) => function.declare,
+        Decl::Var(var) => var.declare,
+        Decl::TsEnum(ts_enum) => ts_enum.declare,
+        Decl::TsModule(ts_module) => ts_module.declare,
+        Decl::TsInterface(_) | Decl::TsTypeAlias(_) => true,
+        Decl::Using(_) => false,
+    }
+}
+
+/// Whether `di` is imported from `react-magnetic-di` and referenced by hand.
+fn uses_manual_di(program: &Program, imports: &[Rc<ImportSpecification>]) -> bool {
+    let di_imports: Vec<_> = imports
+        .iter()
+        .filter(|import| {
+            import.package_name == DI_PACKAGE && import.dependency_imported_symbol == "di"
+        })
+        .cloned()
+        .collect();
+    let mut finder = ImportReferenceFinder {
+        imports: &di_imports,
+        references: vec![],
+    };
+    program.visit_with(&mut finder);
+    !finder.references.is_empty()
+}
+
+/// Partition `replacements` by source package, in order of each package's first use.
+fn group_by_package(replacements: Vec<ActiveReplacement>) -> Vec<Vec<ActiveReplacement>> {
+    let mut groups: Vec<Vec<ActiveReplacement>> = vec![];
+    for replacement in replacements {
+        let group = groups
+            .iter_mut()
+            .find(|group| group[0].import.package_name == replacement.import.package_name);
+        match group {
+            Some(group) => group.push(replacement),
+            None => groups.push(vec![replacement]),
+        }
+    }
+    groups
+}
+
+/// Marks a comment overriding the config for the component it precedes.
+const CONFIG_COMMENT: &str = "@di:config";
+
+const EMOTION_PACKAGE: &str = "@emotion/react";
+
+/// Whether `package_name` is `package` itself or one of its entry points, e.g. `pkg/macro`.
+fn is_package_or_subpath(package_name: &str, package: &str) -> bool {
+    package_name
+        .strip_prefix(package)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Exports of `react` wrapping a component without changing what it renders, injected like the
+/// configured `wrappers` both when imported directly and as members of `React`.
+const REACT_WRAPPERS: [&str; 3] = ["forwardRef", "lazy", "memo"];
+
+/// Superclasses of React class components.
+const REACT_COMPONENT_BASES: [&str; 4] = [
+    "Component",
+    "PureComponent",
+    "React.Component",
+    "React.PureComponent",
+];
+
+/// Whether `class` extends one of React's component base classes. Type arguments as in
+/// `PureComponent<Props>` are kept apart in `super_type_params`, but are unwrapped in case the
+/// superclass is an instantiation expression.
+fn is_react_component_class(class: &Class) -> bool {
+    let Some(mut super_class) = class.super_class.as_deref() else {
+        return false;
+    };
+    while let Expr::TsInstantiation(instantiation) = super_class {
+        super_class = &instantiation.expr;
+    }
+    expr_path(super_class).is_some_and(|path| REACT_COMPONENT_BASES.contains(&path.as_str()))
+}
+
+fn is_super_call(stmt: &Stmt) -> bool {
+    stmt.as_expr()
+        .and_then(|expr_stmt| expr_stmt.expr.as_call())
+        .is_some_and(|call| call.callee.is_super_())
+}
+
+/// Name of a property known without evaluating it, i.e. an identifier or string key.
+fn static_prop_name(key: &PropName) -> Option<&str> {
+    match key {
+        PropName::Ident(ident) => Some(&ident.sym),
+        PropName::Str(str) => Some(&str.value),
+        _ => None,
+    }
+}
+
+/// Keys of `pattern` that are bound to a name as is, with that name, e.g. `A` to `A` and `b` to
+/// `B` for `{ A, b: B }`.
+fn destructured_bindings(pattern: &ObjectPat) -> Vec<(&str, Atom)> {
+    pattern
+        .props
+        .iter()
+        .filter_map(|prop| match prop {
+            ObjectPatProp::Assign(assign) => Some((&*assign.key.sym, assign.key.sym.clone())),
+            ObjectPatProp::KeyValue(key_value) => Some((
+                static_prop_name(&key_value.key)?,
+                key_value.value.as_ident()?.sym.clone(),
+            )),
+            ObjectPatProp::Rest(_) => None,
+        })
+        .collect()
+}
+
+/// Whether `tag` is a styled-components or Emotion tag such as `styled.div`, `styled(Card)` or
+/// `styled.input.attrs({})`.
+fn is_styled_tag(tag: &Expr) -> bool {
+    match tag {
+        Expr::Ident(ident) => ident.sym == *"styled",
+        Expr::Member(member) => is_styled_tag(&member.obj),
+        Expr::Call(call) => call
+            .callee
+            .as_expr()
+            .is_some_and(|callee| is_styled_tag(callee)),
+        _ => false,
+    }
+}
+
+/// Dotted path of a callee such as `forwardRef` or `Object.assign`.
+fn expr_path(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Ident(ident) => Some(ident.sym.to_string()),
+        Expr::Member(member) => {
+            let object = expr_path(&member.obj)?;
+            let property = member.prop.as_ident()?;
+            Some(format!("{object}.{}", property.sym))
+        }
+        _ => None,
+    }
+}
+
+/// Expression referencing a dotted path such as `runtime.registerScope`.
+fn path_expr(path: &str) -> Expr {
+    let mut segments = path.split('.');
+    let root = Expr::from(Ident::from(segments.next().unwrap_or_default()));
+    segments.fold(root, |object, segment| {
+        MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(object),
+            prop: MemberProp::Ident(IdentName::new(segment.into(), DUMMY_SP)),
+        }
+        .into()
+    })
+}
+
+/// Compile the regular expressions of the `excludeScopes` option.
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, ConfigError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|error| ConfigError::InvalidExcludeScope {
+                pattern: pattern.clone(),
+                error,
+            })
+        })
+        .collect()
+}
+
+/// Stands for the import's local name in `TransformConfig::name_template`.
+const NAME_PLACEHOLDER: &str = "{name}";
+
+/// Check the options naming generated code. Bindings have to be valid identifiers other than the
+/// import they replace, `""` as `prefix` would declare `const [Modal] = _di([Modal], Card)`.
+fn validate_names(config: &TransformConfig) -> Result<(), ConfigError> {
+    match &config.name_template {
+        Some(template) if !is_valid_name_template(template) => {
+            return Err(ConfigError::InvalidNameTemplate(template.clone()))
+        }
+        Some(_) => {}
+        None if config.prefix.is_empty() || !is_identifier(&format!("{}Name", config.prefix)) => {
+            return Err(ConfigError::InvalidPrefix(config.prefix.clone()))
+        }
+        None => {}
+    }
+    if !is_identifier(&config.fn_name) {
+        return Err(ConfigError::InvalidFnName(config.fn_name.clone()));
+    }
+    Ok(())
+}
+
+/// Whether `template` names every binding differently, with a valid identifier. A bare
+/// `{name}` would name bindings after the import itself, `const [Modal] = _di([Modal], Card)`
+/// throws as `Modal` is read before it's initialized.
+fn is_valid_name_template(template: &str) -> bool {
+    template.contains(NAME_PLACEHOLDER)
+        && template != NAME_PLACEHOLDER
+        && is_identifier(&template.replace(NAME_PLACEHOLDER, "Name"))
+}
+
+/// Whether `symbol` is a valid identifier, leaving reserved words aside.
+fn is_identifier(symbol: &str) -> bool {
+    let mut chars = symbol.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Kind of a function scope named `scope_symbol`, by React's naming conventions.
+fn function_scope_kind(scope_symbol: &str) -> ScopeKind {
+    let is_hook = scope_symbol
+        .strip_prefix("use")
+        .is_some_and(|rest| rest.is_empty() || starts_with_uppercase(rest));
+    if is_hook {
+        ScopeKind::Hook
+    } else if starts_with_uppercase(scope_symbol) {
+        ScopeKind::Component
+    } else {
+        ScopeKind::Function
+    }
+}
+
+fn starts_with_uppercase(symbol: &str) -> bool {
+    symbol.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// File name without directories or extensions, made safe to use inside an identifier.
+fn file_stem(filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let stem = name.split('.').next().unwrap_or(name);
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// File name without directories or extensions in `PascalCase`, e.g. `UserCard` for
+/// `src/user-card.tsx`.
+fn pascal_case_file_stem(filename: &str) -> String {
+    file_stem(filename)
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// 16-bit FNV-1a hash. It only needs to be stable across platforms and releases, which rules
+/// out `std`'s `DefaultHasher`.
+fn short_hash(input: &str) -> u16 {
+    let hash = input.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+    (hash ^ (hash >> 16)) as u16
+}
+
+/// [`TransformVisitor`] as a [`Fold`], for use without `as_folder`:
+/// `program.fold_with(&mut TransformPlugin::default())`.
+#[derive(Default)]
+pub struct TransformPlugin(pub TransformVisitor);
+
+impl From<TransformConfig> for TransformVisitor {
+    fn from(config: TransformConfig) -> Self {
+        Self::with_config(config)
+    }
+}
+
+impl From<TransformVisitor> for TransformPlugin {
+    fn from(visitor: TransformVisitor) -> Self {
+        Self(visitor)
+    }
+}
+
+impl VisitMut for TransformPlugin {
+    fn visit_mut_program(&mut self, node: &mut Program) {
+        node.visit_mut_with(&mut self.0);
+    }
+
+    // Same as for `Fold`, modules and scripts visited on their own are transformed as a program
+    fn visit_mut_module(&mut self, node: &mut Module) {
+        let mut program = Program::Module(node.take());
+        program.visit_mut_with(&mut self.0);
+        match program {
+            Program::Module(module) => *node = module,
+            Program::Script(_) => unreachable!(),
+        }
+    }
+
+    fn visit_mut_script(&mut self, node: &mut Script) {
+        let mut program = Program::Script(node.take());
+        program.visit_mut_with(&mut self.0);
+        match program {
+            Program::Script(script) => *node = script,
+            Program::Module(_) => unreachable!(),
+        }
+    }
+}
+
+impl Fold for TransformPlugin {
+    fn fold_program(&mut self, mut node: Program) -> Program {
+        node.visit_mut_with(&mut self.0);
+        node
+    }
+
+    // Imports are analysed per program, so modules and scripts folded on their own are
+    // transformed as one.
+    fn fold_module(&mut self, node: Module) -> Module {
+        match self.fold_program(Program::Module(node)) {
+            Program::Module(module) => module,
+            Program::Script(_) => unreachable!(),
+        }
+    }
+
+    fn fold_script(&mut self, node: Script) -> Script {
+        match self.fold_program(Program::Script(node)) {
+            Program::Script(script) => script,
+            Program::Module(_) => unreachable!(),
+        }
+    }
+}
+
+/// An example plugin function with macro support.
+/// `plugin_transform` macro interop pointers into deserialized structs, as well
+/// as returning ptr back to host.
+///
+/// It is possible to opt out from macro by writing transform fn manually
+/// if plugin need to handle low-level ptr directly via
+/// `__transform_plugin_process_impl(
+///     ast_ptr: *const u8, ast_ptr_len: i32,
+///     unresolved_mark: u32, should_enable_comments_proxy: i32) ->
+///     i32 /*  0 for success, fail otherwise.
+///             Note this is only for internal pointer interop result,
+///             not actual transform result */`
+///
+/// This requires manual handling of serialization / deserialization from ptrs.
+/// Refer swc_plugin_macro to see how does it work internally.
+#[plugin_transform]
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+    let config = TransformConfig::from_metadata(&metadata)
+        .unwrap_or_else(|error| panic!("invalid react-magnetic-di plugin config: {error}"));
+    let mut visitor = TransformVisitor::from(config.unwrap_or_default());
+    if let Some(comments) = metadata.comments {
+        visitor = visitor.with_comments(comments);
+    }
+    if let Some(filename) = metadata.get_context(&TransformPluginMetadataContextKind::Filename) {
+        visitor = visitor.with_filename(filename);
+    }
+    if let Some(env) = metadata.get_context(&TransformPluginMetadataContextKind::Env) {
+        visitor = visitor.with_env(env);
+    }
+    program.fold_with(&mut TransformPlugin(visitor))
+}
+
+// An example to test plugin transform.
+// Recommended strategy to test plugin's transform is verify
+// the Visitor's behavior, instead of trying to run `process_transform` with mocks
+// unless explicitly required to do so.
+#[cfg(test)]
+mod test {
+    use super::*;
+    use swc_core::ecma::transforms::testing::{test_inline_input_output, Tester};
+    use swc_core::ecma::visit::as_folder;
+    use swc_ecma_parser::{EsSyntax, Syntax, TsSyntax};
+
+    /// Run `visitor` over a JSX module and print the result, comments included.
+    fn transform(visitor: TransformVisitor, input: &str) -> String {
+        Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let module = tester.apply_transform(as_folder(visitor), "input.js", syntax, input)?;
+            Ok(tester.print(&module, &tester.comments.clone()))
+        })
+    }
+
+    /// Parse `input` as a JSX module and print it back as is.
+    fn print(input: &str) -> String {
+        Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let module = tester.with_parser("input.js", syntax, input, |p| p.parse_module())?;
+            Ok(tester.print(&module, &tester.comments.clone()))
+        })
+    }
+
+    #[test]
+    fn test_should_work_in_class_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+class MyComponent extends Component {
+    render() {
+        return <Modal />;
+    }
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+class MyComponent extends Component {
+    render() {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_function_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_arrow_function_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+const MyComponent = () => {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import Modal from 'modal';
+
+const MyComponent = () => {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hoist_render_prop_references_to_the_component() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import DataLoader from 'data-loader';
+import Chart from 'chart';
+import DarkTheme from 'themes';
+
+const MyComponent = () => {
+    return <DataLoader render={(data) => <Chart data={data} theme={DarkTheme} />} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import DataLoader from 'data-loader';
+import Chart from 'chart';
+import DarkTheme from 'themes';
+
+const MyComponent = () => {
+    const [_DataLoader, _Chart, _DarkTheme] = _di([DataLoader, Chart, DarkTheme], MyComponent);
+    return <_DataLoader render={(data) => <_Chart data={data} theme={_DarkTheme} />} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_inject_into_render_prop_function_expressions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import DataLoader from 'data-loader';
+import Chart from 'chart';
+
+function MyComponent() {
+    return <DataLoader render={function (data) { return <Chart data={data} />; }} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import DataLoader from 'data-loader';
+import Chart from 'chart';
+
+function MyComponent() {
+    const [_DataLoader, _Chart] = _di([DataLoader, Chart], MyComponent);
+    return <_DataLoader render={function (data) { return <_Chart data={data} />; }} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hoist_children_as_function_references_to_the_component() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import List from 'list';
+import Row from 'row';
+
+function MyComponent() {
+    return <List>{(item) => <Row item={item} />}</List>;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import List from 'list';
+import Row from 'row';
+
+function MyComponent() {
+    const [_List, _Row] = _di([List, Row], MyComponent);
+    return <_List>{(item) => <_Row item={item} />}</_List>;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hash_generated_names() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    hash_names: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal_cee8] = _di([Modal], MyComponent);
+    return <_Modal_cee8 />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_emit_runtime_check_when_enabled() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    emit_runtime_check: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}
+
+function Empty() {
+    return null;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function MyComponent() {
+    if (typeof _di !== 'function') throw new Error('react-magnetic-di: `_di` is not defined, make sure the di helper is in scope');
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}
+
+function Empty() {
+    return null;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_preserve_whitespace_around_expression_children() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Wrapper> {Modal} </Wrapper>;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <Wrapper> {_Modal} </Wrapper>;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_object_assign_wrapped_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Chip from 'chip';
+import { sizes } from 'chip';
+
+export const Badge = Object.assign((props) => {
+    return <Chip {...props} />;
+}, { sizes });"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Chip from 'chip';
+import { sizes } from 'chip';
+
+export const Badge = Object.assign((props) => {
+    const [_Chip] = _di([Chip], Badge);
+    return <_Chip {...props} />;
+}, { sizes });"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_object_freeze_wrapped_function_expressions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Chip from 'chip';
+
+const Badge = Object.freeze(function Badge(props) {
+    return <Chip {...props} />;
+});"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Chip from 'chip';
+
+const Badge = Object.freeze(function Badge(props) {
+    const [_Chip] = _di([Chip], Badge);
+    return <_Chip {...props} />;
+});"#,
+        );
+    }
+
+    #[test]
+    fn test_should_only_unwrap_configured_wrappers() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    wrappers: vec![],
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Chip from 'chip';
+
+const Badge = Object.assign((props) => {
+    return <Chip {...props} />;
+}, {});"#,
+            // Output codes after transformed with plugin
+            r#"
+import Chip from 'chip';
+
+const Badge = Object.assign((props) => {
+    return <Chip {...props} />;
+}, {});"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_touch_module_level_route_tables() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Page from 'page';
+
+export const route = {
+    path: '/x',
+    component: () => <Page />,
+    render: function () { return <Page />; },
+};"#,
+            // Output codes after transformed with plugin
+            r#"
+import Page from 'page';
+
+export const route = {
+    path: '/x',
+    component: () => <Page />,
+    render: function () { return <Page />; },
+};"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_replace_inside_const_enum_members() {
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+import { Small } from 'sizes';
+
+function MyComponent() {
+    const enum Size { Small = 1, Large = Small * 2 }
+    return <Modal size={Size.Large} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import { Small } from 'sizes';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    const enum Size { Small = 1, Large = Small * 2 }
+    return <_Modal size={Size.Large} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_replace_inside_enum_initializers() {
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+import { sizes } from 'sizes';
+
+function MyComponent() {
+    enum Size { Small = sizes.small, Large = Small * 2 }
+    return <Modal size={Size.Large} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import { sizes } from 'sizes';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    enum Size { Small = sizes.small, Large = Small * 2 }
+    return <_Modal size={Size.Large} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hoist_references_inside_loops() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function MyComponent({ items }) {
+    const list = [];
+    for (const x of items) {
+        list.push(<Modal item={x} />);
+    }
+    return list;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function MyComponent({ items }) {
+    const [_Modal] = _di([Modal], MyComponent);
+    const list = [];
+    for (const x of items) {
+        list.push(<_Modal item={x} />);
+    }
+    return list;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_keep_pure_annotations_on_wrapped_components() {
+        let visitor = TransformVisitor::with_config(TransformConfig {
+            wrappers: vec!["memo".into()],
+            ..Default::default()
+        });
+        let output = transform(
+            visitor,
+            r#"
+import Box from 'box';
+
+const Card = /* @__PURE__ */ memo(() => {
+    return <Box />;
+});"#,
+        );
+
+        assert_eq!(output.matches("/* @__PURE__ */").count(), 1);
+        assert!(output.contains("const Card = /* @__PURE__ */ memo(()=>{"));
+        assert!(output.contains("{\n    const [_Box] = _di(["));
+    }
+
+    #[test]
+    fn test_should_keep_comments_of_expression_bodies() {
+        let output = transform(
+            TransformVisitor::default(),
+            r#"
+import Modal from 'modal';
+
+const Thing = () => /*#__PURE__*/ createThing(Modal);"#,
+        );
+
+        assert!(output.contains("const Thing = ()=>{\n"));
+        assert!(output.contains("return /*#__PURE__*/ createThing(_Modal);"));
+        assert_eq!(output.matches("/*#__PURE__*/").count(), 1);
+    }
+
+    #[test]
+    fn test_should_work_in_custom_component_factories() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    custom_component_factories: vec!["defineComponent".into()],
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Modal from 'modal';
+import { format } from 'format';
+
+const MyComponent = defineComponent({
+    name: format('my-component'),
+    render() {
+        return <Modal />;
+    },
+});"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import { format } from 'format';
+
+const MyComponent = defineComponent({
+    name: format('my-component'),
+    render() {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    },
+});"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_async_default_exports() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import DataView from 'data-view';
+import { fetchData } from 'api';
+
+export default async function Page() {
+    const data = await fetchData();
+    return <DataView data={data} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import DataView from 'data-view';
+import { fetchData } from 'api';
+
+export default async function Page() {
+    const [_fetchData, _DataView] = _di([fetchData, DataView], Page);
+    const data = await _fetchData();
+    return <_DataView data={data} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_produce_identical_output_across_runs() {
+        let input = r#"
+import Modal from 'modal';
+import { Button, useQuery } from 'ui';
+
+function MyComponent() {
+    const data = useQuery();
+    return <Modal><Button data={data} /></Modal>;
+}"#;
+        let expected = transform(TransformVisitor::default(), input);
+        for _ in 0..50 {
+            assert_eq!(transform(TransformVisitor::default(), input), expected);
+        }
+    }
+
+    #[test]
+    fn test_should_not_depend_on_import_order() {
+        let component = r#"
+function MyComponent() {
+    const data = useQuery();
+    return <Modal><Button data={data} /></Modal>;
+}"#;
+        let body = |imports: &str| {
+            let output = transform(
+                TransformVisitor::default(),
+                &format!("{imports}{component}"),
+            );
+            output[output.find("function").unwrap()..].to_string()
+        };
+
+        assert_eq!(
+            body("import Modal from 'modal';\nimport { Button, useQuery } from 'ui';\n"),
+            body("import { useQuery, Button } from 'ui';\nimport Modal from 'modal';\n"),
+        );
+    }
+
+    #[test]
+    fn test_should_normalize_path_separators_in_filename() {
+        // The anonymous default export is named after the file
+        let input = r#"
+import Modal from 'modal';
+
+export default () => <Modal />;"#;
+        let visitor = |filename: &str| TransformVisitor::default().with_filename(filename);
+
+        let output = transform(visitor("src\\components\\Card.tsx"), input);
+        assert!(output.contains("const [_Modal] = _di([\n        Modal\n    ], Card);"));
+        assert_eq!(transform(visitor("src/components/Card.tsx"), input), output);
+    }
+
+    #[test]
+    fn test_should_only_wrap_capitalized_imports_when_configured() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    capitalized_only: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Modal from 'modal';
+import { useQuery } from 'data';
+
+function MyComponent() {
+    const data = useQuery();
+    return <Modal data={data} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import { useQuery } from 'data';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    const data = useQuery();
+    return <_Modal data={data} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_transform_di_enabled_files_when_required() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    require_di_import: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { di } from 'react-magnetic-di';
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di } from 'react-magnetic-di';
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal] = di([Modal], MyComponent);
+    return <_Modal />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_transform_files_importing_only_injectable_when_required() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    require_di_import: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { injectable } from 'react-magnetic-di';
+import Modal from 'modal';
+
+export const ModalMock = injectable(Modal, () => null);
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { injectable } from 'react-magnetic-di';
+import Modal from 'modal';
+
+export const ModalMock = injectable(Modal, () => null);
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_skip_files_without_di_import_when_required() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    require_di_import: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { withDi } from 'react-magnetic-di';
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { withDi } from 'react-magnetic-di';
+import Modal from 'modal';
+
+function MyComponent() {
+    return <Modal />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hoist_references_inside_async_event_handlers() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function MyComponent() {
+    return <button onClick={async () => { await Modal.open(); }} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <button onClick={async () => { await _Modal.open(); }} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_leave_dynamic_imports_alone_by_default() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+async function MyComponent() {
+    const { HeavyEditor } = await import('./HeavyEditor');
+    return <HeavyEditor />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+async function MyComponent() {
+    const { HeavyEditor } = await import('./HeavyEditor');
+    return <HeavyEditor />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_dynamic_imports_in_async_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    dynamic_imports: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Spinner from 'spinner';
+
+async function MyComponent() {
+    const { HeavyEditor } = await import('./HeavyEditor');
+    return <HeavyEditor fallback={<Spinner />} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Spinner from 'spinner';
+
+async function MyComponent() {
+    const [_Spinner] = _di([Spinner], MyComponent);
+    const { HeavyEditor } = await import('./HeavyEditor');
+    const [_HeavyEditor] = _di([HeavyEditor], MyComponent);
+    return <_HeavyEditor fallback={<_Spinner />} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_dynamic_imports_in_event_handlers() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    dynamic_imports: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+function MyComponent() {
+    return <button onClick={async () => {
+        const { default: Editor } = await import('./Editor');
+        Editor.open();
+    }} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+function MyComponent() {
+    return <button onClick={async () => {
+        const { default: Editor } = await import('./Editor');
+        const [_Editor] = _di([Editor], MyComponent);
+        _Editor.open();
+    }} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_chained_assignment_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+let B;
+const A = B = () => {
+    return <Modal />;
+};"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+let B;
+const A = B = () => {
+    const [_Modal] = _di([Modal], A);
+    return <_Modal />;
+};"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_into_constructors_after_super() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import Service from 'service';
+import Modal from 'modal';
+
+class MyComponent extends Component {
+    constructor(props) {
+        super(Service.defaults(props));
+        this.service = new Service();
+    }
+
+    render() {
+        return <Modal />;
+    }
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import Service from 'service';
+import Modal from 'modal';
+
+class MyComponent extends Component {
+    constructor(props) {
+        super(Service.defaults(props));
+        const [_Service] = _di([Service], MyComponent);
+        this.service = new _Service();
+    }
+
+    render() {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_wrap_computed_key_values_in_spread_attributes() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+import Renderer from 'renderer';
+
+function MyComponent({ dynKey }) {
+    return <Modal {...{ [dynKey]: Renderer }} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import Renderer from 'renderer';
+
+function MyComponent({ dynKey }) {
+    const [_Modal, _Renderer] = _di([Modal, Renderer], MyComponent);
+    return <_Modal {...{ [dynKey]: _Renderer }} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_skip_manually_configured_files() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    skip_manually_configured: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { di } from 'react-magnetic-di';
+import Modal from 'modal';
+import Button from 'button';
+
+function MyComponent() {
+    const [_Modal] = di([Modal], MyComponent);
+    return <_Modal><Button /></_Modal>;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di } from 'react-magnetic-di';
+import Modal from 'modal';
+import Button from 'button';
+
+function MyComponent() {
+    const [_Modal] = di([Modal], MyComponent);
+    return <_Modal><Button /></_Modal>;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_only_leave_imports_alone_where_nested_functions_shadow_them() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import Modal from 'modal';
+
+function Card({ items }) {
+    const renderItem = () => {
+        const Modal = () => null;
+        return <Modal />;
+    };
+    const rows = items.map(function (Modal) {
+        return <Modal />;
+    });
+    return <Modal>{renderItem()}{rows}</Modal>;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+
+function Card({ items }) {
+    const [_Modal] = _di([Modal], Card);
+    const renderItem = () => {
+        const Modal = () => null;
+        return <Modal />;
+    };
+    const rows = items.map(function (Modal) {
+        return <Modal />;
+    });
+    return <_Modal>{renderItem()}{rows}</_Modal>;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_replace_imports_shadowed_by_parameters() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { theme } from './theme';
+import Modal from 'modal';
+
+function Card({ theme }) {
+    return <Modal theme={theme} />;
+}
+
+function List(...theme) {
+    return <Modal theme={theme} />;
+}
+
+const Row = ({ palette: theme }) => {
+    return <Modal theme={theme} />;
+};
+
+function Header() {
+    return <Modal theme={theme} />;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { theme } from './theme';
+import Modal from 'modal';
+
+function Card({ theme }) {
+    const [_Modal] = _di([Modal], Card);
+    return <_Modal theme={theme} />;
+}
+
+function List(...theme) {
+    const [_Modal] = _di([Modal], List);
+    return <_Modal theme={theme} />;
+}
+
+const Row = ({ palette: theme }) => {
+    const [_Modal] = _di([Modal], Row);
+    return <_Modal theme={theme} />;
+};
+
+function Header() {
+    const [_Modal, _theme] = _di([Modal, theme], Header);
+    return <_Modal theme={_theme} />;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_exclude_emotion_imports_with_emotion_pragma() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |tester| {
+                as_folder(
+                    TransformVisitor::with_config(TransformConfig {
+                        emotion_compat: true,
+                        ..Default::default()
+                    })
+                    .with_comments(tester.comments.clone()),
+                )
+            },
+            // Input codes
+            r#"
+/** @jsxImportSource @emotion/react */
+import { css, ClassNames } from '@emotion/react';
+import Modal from 'modal';
+
+function MyComponent() {
+    return <ClassNames><Modal css={css({ color: 'red' })} /></ClassNames>;
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { css, ClassNames } from '@emotion/react';
+import Modal from 'modal';
+
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <ClassNames><_Modal css={css({ color: 'red' })} /></ClassNames>;
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_redirect_to_existing_import() {
+        let config = TransformConfig {
+            redirects: [("./client#client".into(), "./api#apiClient".into())].into(),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { client } from './client';
+import { apiClient as api } from './api';
+function MyComponent() {
+    return client.fetch(api);
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { client } from './client';
+import { apiClient as api } from './api';
+function MyComponent() {
+    const [_client, _api] = _di([api, api], MyComponent);
+    return _client.fetch(_api);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_add_missing_import_for_redirect() {
+        let config = TransformConfig {
+            redirects: [("./client#client".into(), "./api#apiClient".into())].into(),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { client } from './client';
+import { Button } from './button';
+const label = 'Load';
+function MyComponent() {
+    return <Button onClick={client.fetch}>{label}</Button>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { client } from './client';
+import { Button } from './button';
+import { apiClient } from "./api";
+const label = 'Load';
+function MyComponent() {
+    const [_Button, _client] = _di([Button, apiClient], MyComponent);
+    return <_Button onClick={_client.fetch}>{label}</_Button>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_clash_with_declarations_when_adding_redirect_import() {
+        let config = TransformConfig {
+            redirects: [("./client#client".into(), "./api#apiClient".into())].into(),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { client } from './client';
+const apiClient = 1;
+function MyComponent() {
+    return <div onClick={client.fetch}>{apiClient}</div>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { client } from './client';
+import { apiClient as _apiClient } from "./api";
+const apiClient = 1;
+function MyComponent() {
+    const [_client] = _di([_apiClient], MyComponent);
+    return <div onClick={_client.fetch}>{apiClient}</div>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_destructuring_rename_of_import() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { open } from './actions';
+function MyComponent() {
+    const { open: openModal, close: closeModal = open } = Modal;
+    return <button onClick={openModal} onBlur={closeModal} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { open } from './actions';
+function MyComponent() {
+    const [_open, _Modal] = _di([open, Modal], MyComponent);
+    const { open: openModal, close: closeModal = _open } = _Modal;
+    return <button onClick={openModal} onBlur={closeModal} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_group_by_package() {
+        let config = TransformConfig {
+            group_by: GroupBy::Package,
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { Modal, Dialog } from '@acme/ui';
+import { useQuery } from '@acme/data';
+import { useTranslation } from 'react-i18next';
+import { formatTitle } from './utils';
+function Card() {
+    const { data } = useQuery();
+    const { t } = useTranslation();
+    return <Modal title={formatTitle(t(data.title))}><Dialog /></Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal, Dialog } from '@acme/ui';
+import { useQuery } from '@acme/data';
+import { useTranslation } from 'react-i18next';
+import { formatTitle } from './utils';
+function Card() {
+    const [_useQuery] = _di([useQuery], Card);
+    const [_useTranslation] = _di([useTranslation], Card);
+    const [_Modal, _Dialog] = _di([Modal, Dialog], Card);
+    const [_formatTitle] = _di([formatTitle], Card);
+    const { data } = _useQuery();
+    const { t } = _useTranslation();
+    return <_Modal title={_formatTitle(t(data.title))}><_Dialog /></_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_hoisted_inner_function_declaration() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { useOpen } from './hooks';
+function Card() {
+    const content = renderModal();
+    function renderModal() {
+        return <Modal open={isOpen} />;
+    }
+    const isOpen = useOpen();
+    return content;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { useOpen } from './hooks';
+function Card() {
+    const [_Modal, _useOpen] = _di([Modal, useOpen], Card);
+    const content = renderModal();
+    function renderModal() {
+        return <_Modal open={isOpen} />;
+    }
+    const isOpen = _useOpen();
+    return content;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_static_class_methods() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export class Dialogs {
+    static make() {
+        return <Modal />;
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+export class Dialogs {
+    static make() {
+        const [_Modal] = _di([Modal], Dialogs);
+        return <_Modal />;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_override_config_from_comment() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |tester| as_folder(TransformVisitor::default().with_comments(tester.comments.clone())),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+/* @di:config { "prefix": "__", "fnName": "myDi" } */
+function Dialog() {
+    return <Modal />;
+}
+/* @di:config { "fnName": "otherDi" } */
+export function Popup() {
+    return <Modal />;
+}
+function Sheet() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as myDi, di as otherDi, di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function Dialog() {
+    const [__Modal] = myDi([Modal], Dialog);
+    return <__Modal />;
+}
+export function Popup() {
+    const [_Modal] = otherDi([Modal], Popup);
+    return <_Modal />;
+}
+function Sheet() {
+    const [_Modal] = _di([Modal], Sheet);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_ignore_invalid_config_comment() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |tester| as_folder(TransformVisitor::default().with_comments(tester.comments.clone())),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+/* @di:config { prefix: "__" } */
+function Dialog() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function Dialog() {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_ignore_config_comments_with_file_wide_or_invalid_options() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |tester| as_folder(TransformVisitor::default().with_comments(tester.comments.clone())),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+/* @di:config { "prefix": "__", "packages": ["modal"], "capitalizedOnly": true } */
+function Dialog() {
+    return <Modal />;
+}
+/* @di:config { "prefix": "__", "excludeScopes": ["("] } */
+function Drawer() {
+    return <Modal />;
+}
+/* @di:config { "nameTemplate": "mocked" } */
+function Popover() {
+    return <Modal />;
+}
+/* @di:config { "prefix": "" } */
+function Tooltip() {
+    return <Modal />;
+}
+/* @di:config { "fnName": "my-di" } */
+function Menu() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function Dialog() {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal />;
+}
+function Drawer() {
+    const [_Modal] = _di([Modal], Drawer);
+    return <_Modal />;
+}
+function Popover() {
+    const [_Modal] = _di([Modal], Popover);
+    return <_Modal />;
+}
+function Tooltip() {
+    const [_Modal] = _di([Modal], Tooltip);
+    return <_Modal />;
+}
+function Menu() {
+    const [_Modal] = _di([Modal], Menu);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_keep_unset_options_when_merging() {
+        let config = TransformConfig {
+            hash_names: true,
+            ..Default::default()
+        }
+        .merge_with(PartialTransformConfig {
+            fn_name: Some("myDi".into()),
+            ..Default::default()
+        });
+        assert!(config.hash_names);
+        assert_eq!(config.fn_name, "myDi");
+        assert_eq!(config.prefix, "_");
+    }
+
+    #[test]
+    fn test_should_resolve_relative_imports_with_package_for_path() {
+        let config = TransformConfig {
+            group_by: GroupBy::Package,
+            package_for_path: [("src/ui".into(), "@corp/design-system".into())].into(),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(
+                    TransformVisitor::with_config(config.clone())
+                        .with_filename("src/features/cards/Card.jsx"),
+                )
+            },
+            // Input codes
+            r#"
+import { Modal } from '../../ui';
+import { Icon } from '@corp/design-system';
+import { Tooltip } from './../../ui/tooltip';
+import { Chip } from '../../ui-kit';
+function Card() {
+    return <Modal><Chip /><Icon /><Tooltip /></Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from '../../ui';
+import { Icon } from '@corp/design-system';
+import { Tooltip } from './../../ui/tooltip';
+import { Chip } from '../../ui-kit';
+function Card() {
+    const [_Modal, _Icon, _Tooltip] = _di([Modal, Icon, Tooltip], Card);
+    const [_Chip] = _di([Chip], Card);
+    return <_Modal><_Chip /><_Icon /><_Tooltip /></_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_resolve_package_for_path_with_absolute_filename() {
+        let config = TransformConfig {
+            group_by: GroupBy::Package,
+            package_for_path: [("./src/ui/".into(), "@corp/design-system".into())].into(),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(
+                    TransformVisitor::with_config(config.clone())
+                        .with_filename("/home/dev/app/src/pages/Home.jsx"),
+                )
+            },
+            // Input codes
+            r#"
+import { Modal } from '../ui/modal';
+import { Icon } from '@corp/design-system';
+function Home() {
+    return <Modal><Icon /></Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from '../ui/modal';
+import { Icon } from '@corp/design-system';
+function Home() {
+    const [_Modal, _Icon] = _di([Modal, Icon], Home);
+    return <_Modal><_Icon /></_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_detect_module_syntax() {
+        let cases = [
+            ("import { a } from 'a';", "input.js", ModuleSyntax::Esm),
+            ("export const a = 1;", "input.cjs", ModuleSyntax::Esm),
+            (
+                "const a = require('a');",
+                "input.js",
+                ModuleSyntax::CommonJs,
+            ),
+            ("module.exports = {};", "input.mjs", ModuleSyntax::CommonJs),
+            ("exports.a = 1;", "input.js", ModuleSyntax::CommonJs),
+            (
+                "import a = require('a');",
+                "input.ts",
+                ModuleSyntax::CommonJs,
+            ),
+            // `require` inside a function says nothing about the module itself
+            (
+                "function load() { return require('a'); }",
+                "input.js",
+                ModuleSyntax::Esm,
+            ),
+            // No imports at all
+            ("const a = 1;", "input.js", ModuleSyntax::Esm),
+            ("const a = 1;", "input.cjs", ModuleSyntax::CommonJs),
+            ("const a = 1;", "input.cts", ModuleSyntax::CommonJs),
+            ("const a = 1;", "input.mjs", ModuleSyntax::Esm),
+        ];
+        for (input, filename, expected) in cases {
+            Tester::run(|tester| {
+                let syntax = Syntax::Typescript(Default::default());
+                let module =
+                    tester.with_parser(filename, syntax, input, |parser| parser.parse_module())?;
+                let program = Program::Module(module);
+                assert_eq!(
+                    detect_module_syntax(&program, Some(filename)),
+                    expected,
+                    "{input} in {filename}"
+                );
+                Ok(())
+            });
+        }
+    }
+
+    #[test]
+    fn test_should_require_react_component_base_with_type_arguments() {
+        let config = TransformConfig {
+            require_react_component_base: true,
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import React, { PureComponent } from 'react';
+import { Modal } from './modal';
+import { formatTitle } from './format';
+class MyComponent extends PureComponent<Props, State> {
+    render() {
+        return <Modal />;
+    }
+}
+class Other extends React.Component<Props> {
+    render() {
+        return <Modal />;
+    }
+}
+class Store {
+    title() {
+        return formatTitle(this.name);
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { PureComponent } from 'react';
+import { Modal } from './modal';
+import { formatTitle } from './format';
+class MyComponent extends PureComponent<Props, State> {
+    render() {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    }
+}
+class Other extends React.Component<Props> {
+    render() {
+        const [_Modal] = _di([Modal], Other);
+        return <_Modal />;
+    }
+}
+class Store {
+    title() {
+        return formatTitle(this.name);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_emit_scope_setup_call() {
+        let config = TransformConfig {
+            scope_setup_call: Some("__registerScope".into()),
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { Modal, Dialog } from './modal';
+function MyComponent() {
+    return <Modal><Dialog /></Modal>;
+}
+const Other = () => {
+    return <Dialog />;
+};
+function Plain() {
+    return <div />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal, Dialog } from './modal';
+function MyComponent() {
+    __registerScope("MyComponent");
+    const [_Modal, _Dialog] = _di([Modal, Dialog], MyComponent);
+    return <_Modal><_Dialog /></_Modal>;
+}
+const Other = () => {
+    __registerScope("Other");
+    const [_Dialog] = _di([Dialog], Other);
+    return <_Dialog />;
+};
+function Plain() {
+    return <div />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_emit_scope_setup_call_once_with_dynamic_imports() {
+        let config = TransformConfig {
+            scope_setup_call: Some("__registerScope".into()),
+            emit_runtime_check: true,
+            dynamic_imports: true,
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import Spinner from 'spinner';
+async function MyComponent() {
+    const { HeavyEditor } = await import('./HeavyEditor');
+    return <HeavyEditor fallback={<Spinner />} />;
+}
+async function Lazy() {
+    const { HeavyEditor } = await import('./HeavyEditor');
+    return <HeavyEditor />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Spinner from 'spinner';
+async function MyComponent() {
+    __registerScope("MyComponent");
+    if (typeof _di !== 'function') throw new Error('react-magnetic-di: `_di` is not defined, make sure the di helper is in scope');
+    const [_Spinner] = _di([Spinner], MyComponent);
+    const { HeavyEditor } = await import('./HeavyEditor');
+    const [_HeavyEditor] = _di([HeavyEditor], MyComponent);
+    return <_HeavyEditor fallback={<_Spinner />} />;
+}
+async function Lazy() {
+    __registerScope("Lazy");
+    if (typeof _di !== 'function') throw new Error('react-magnetic-di: `_di` is not defined, make sure the di helper is in scope');
+    const { HeavyEditor } = await import('./HeavyEditor');
+    const [_HeavyEditor] = _di([HeavyEditor], Lazy);
+    return <_HeavyEditor />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_fold_without_as_folder() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| TransformPlugin::default(),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_visit_modules_without_program() {
+        let output = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let input = "import { Modal } from './modal';\nconst Card = () => <Modal />;";
+            let mut module = tester.with_parser("input.js", syntax, input, |p| p.parse_module())?;
+            module.visit_mut_with(&mut TransformPlugin::default());
+            Ok(tester.print(&module, &tester.comments.clone()))
+        });
+        assert!(output.contains("import { di as _di } from \"react-magnetic-di\";"));
+        assert!(output.contains("const [_Modal] = _di([\n        Modal\n    ], Card);"));
+    }
+
+    #[test]
+    fn test_should_visit_scripts_without_program() {
+        let output = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let input = "async function Card() {\n    const { Modal } = await import('./modal');\n    return <Modal />;\n}";
+            let mut script = tester.with_parser("input.js", syntax, input, |p| p.parse_script())?;
+            script.visit_mut_with(&mut TransformPlugin(TransformVisitor::with_config(
+                TransformConfig {
+                    dynamic_imports: true,
+                    ..Default::default()
+                },
+            )));
+            let module = Module {
+                span: script.span,
+                body: script.body.into_iter().map(ModuleItem::Stmt).collect(),
+                shebang: None,
+            };
+            Ok(tester.print(&module, &tester.comments.clone()))
+        });
+        assert!(output.contains("const _di = require(\"react-magnetic-di\").di;"));
+        assert!(output.contains("const [_Modal] = _di([\n        Modal\n    ], Card);"));
+    }
+
+    #[test]
+    fn test_should_work_with_template_literal_in_jsx_prop() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { Name, formatName } from './name';
+function MyComponent() {
+    return <Modal title={`Hello ${Name} (${`${formatName(Name)}`})`} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { Name, formatName } from './name';
+function MyComponent() {
+    const [_Modal, _Name, _formatName] = _di([Modal, Name, formatName], MyComponent);
+    return <_Modal title={`Hello ${_Name} (${`${_formatName(_Name)}`})`} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_string_keyed_class_methods() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+class MyComponent extends React.Component {
+    "render"() {
+        return <Modal />;
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+class MyComponent extends React.Component {
+    "render"() {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_skip_computed_keyed_class_methods() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { RENDER } from './keys';
+class MyComponent extends React.Component {
+    [RENDER]() {
+        return <Modal />;
+    }
+    render() {
+        return this[RENDER]();
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { RENDER } from './keys';
+class MyComponent extends React.Component {
+    [RENDER]() {
+        return <Modal />;
+    }
+    render() {
+        const [_RENDER] = _di([RENDER], MyComponent);
+        return this[_RENDER]();
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_spread_of_imported_prop_factory() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { getFieldProps } from './forms';
+import { useForm } from './hooks';
+function SignupForm() {
+    const form = useForm();
+    return <form>
+        <input {...getFieldProps(form, 'name')} />
+        <input {...getFieldProps(form, 'email')} />
+        <input {...getFieldProps(form, 'password')} type="password" />
+        <input {...getFieldProps(form, 'phone')} />
+        <input {...getFieldProps(form, 'address')} />
+    </form>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { getFieldProps } from './forms';
+import { useForm } from './hooks';
+function SignupForm() {
+    const [_useForm, _getFieldProps] = _di([useForm, getFieldProps], SignupForm);
+    const form = _useForm();
+    return <form>
+        <input {..._getFieldProps(form, 'name')} />
+        <input {..._getFieldProps(form, 'email')} />
+        <input {..._getFieldProps(form, 'password')} type="password" />
+        <input {..._getFieldProps(form, 'phone')} />
+        <input {..._getFieldProps(form, 'address')} />
+    </form>;
+}
+"#,
+        );
+    }
+
+    /// Factories created at module level with the deprecated `React.createFactory` aren't
+    /// injected: `Modal` is referenced outside of any component, and `createModal` is a local
+    /// binding rather than an import. Mocking `Modal` therefore has no effect on components using
+    /// `createModal`, which have to render `<Modal />` or call `createFactory` themselves instead.
+    #[test]
+    fn test_should_not_inject_create_factory() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React from 'react';
+import { Modal } from './modal';
+const createModal = React.createFactory(Modal);
+function MyComponent(props) {
+    return createModal(props);
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import React from 'react';
+import { Modal } from './modal';
+const createModal = React.createFactory(Modal);
+function MyComponent(props) {
+    return createModal(props);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_only_change_imports_by_adding_one() {
+        let input = r#"
+import './polyfills';
+// Sorted by the linter, keep as is
+import React, { useState as useLocalState } from 'react';
+import * as styles from './styles.css';
+import { client } from './client';
+
+const MyComponent = () => {
+    const [open] = useLocalState(false);
+    return <div className={styles.root}>{open && client.name}</div>;
+};"#;
+        let imports = |output: String| {
+            output[..output.find("const MyComponent").unwrap()]
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let config = TransformConfig {
+            redirects: [("./client#client".into(), "./api#apiClient".into())].into(),
+            ..Default::default()
+        };
+
+        let original = imports(transform(TransformVisitor::default(), input));
+        let mut expected = original.clone();
+        expected.insert(6, r#"import { apiClient } from "./api";"#.to_string());
+        assert_eq!(
+            imports(transform(TransformVisitor::with_config(config), input)),
+            expected
+        );
+        assert_eq!(
+            original,
+            [
+                r#"import { di as _di } from "react-magnetic-di";"#,
+                "import './polyfills';",
+                "// Sorted by the linter, keep as is",
+                "import React, { useState as useLocalState } from 'react';",
+                "import * as styles from './styles.css';",
+                "import { client } from './client';",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_forward_ref_imported_directly() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { forwardRef } from 'react';
+import { forwardRef as fancyRef } from 'fancy';
+import { Modal } from './modal';
+const MyComponent = forwardRef((props, ref) => {
+    return <Modal ref={ref} />;
+});
+const Other = fancyRef((props, ref) => {
+    return <Modal ref={ref} />;
+});
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { forwardRef } from 'react';
+import { forwardRef as fancyRef } from 'fancy';
+import { Modal } from './modal';
+const MyComponent = forwardRef((props, ref) => {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal ref={ref} />;
+});
+const Other = fancyRef((props, ref) => {
+    return <Modal ref={ref} />;
+});
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_hoist_map_callback_references_in_returned_jsx() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Wrapper } from './wrapper';
+import { Modal } from './modal';
+function MyComponent({ items }) {
+    return <Wrapper>{items.map((item) => <Modal key={item.id} />)}{items.map(function (item) {
+        return <Modal key={item.id} />;
+    })}</Wrapper>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Wrapper } from './wrapper';
+import { Modal } from './modal';
+function MyComponent({ items }) {
+    const [_Wrapper, _Modal] = _di([Wrapper, Modal], MyComponent);
+    return <_Wrapper>{items.map((item) => <_Modal key={item.id} />)}{items.map(function (item) {
+        return <_Modal key={item.id} />;
+    })}</_Wrapper>;
+}
+"#,
+        );
+    }
+
+    /// Parse `input` as a JSX module and return what `analyze_dependencies` reports for it along
+    /// with the transformed output.
+    fn analyze_and_transform(input: &str) -> (Vec<ComponentDeps>, String) {
+        let mut reports = vec![];
+        let output = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let module = tester.with_parser("input.js", syntax, input, |p| p.parse_module())?;
+            let program = Program::Module(module);
+            reports = analyze_dependencies(&program, &TransformConfig::default()).unwrap();
+            let program = program.fold_with(&mut TransformPlugin::default());
+            Ok(tester.print(&program.expect_module(), &tester.comments.clone()))
+        });
+        (reports, output)
+    }
+
+    #[test]
+    fn test_should_analyze_dependencies_like_transform() {
+        let (reports, output) = analyze_and_transform(
+            r#"
+import { Modal } from './modal';
+import Button from 'ui/button';
+import { useQuery as useData } from '@acme/data';
+function MyComponent() {
+    const data = useData();
+    return <Modal><Button>{data}</Button></Modal>;
+}
+const Other = () => {
+    return <Button />;
+};
+class Legacy extends React.Component {
+    render() {
+        return <Modal />;
+    }
+}
+"#,
+        );
+
+        let summary: Vec<_> = reports
+            .iter()
+            .map(|report| {
+                let deps: Vec<_> = report
+                    .dependencies
+                    .iter()
+                    .map(|dep| format!("{}:{}:{}", dep.package, dep.imported, dep.local))
+                    .collect();
+                (report.name.to_string(), deps)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "MyComponent".to_string(),
+                    vec![
+                        "@acme/data:useQuery:useData".to_string(),
+                        "./modal:Modal:Modal".to_string(),
+                        "ui/button:Button:Button".to_string(),
+                    ]
+                ),
+                (
+                    "Other".to_string(),
+                    vec!["ui/button:Button:Button".to_string()]
+                ),
+                (
+                    "Legacy".to_string(),
+                    vec!["./modal:Modal:Modal".to_string()]
+                ),
+            ]
+        );
+
+        // Every reported dependency is injected into its component, in the reported order
+        let output: String = output.split_whitespace().collect();
+        for report in &reports {
+            let name = &report.name;
+            let locals = report.dependencies.iter().map(|dep| dep.local.to_string());
+            let bindings: Vec<_> = locals.clone().map(|local| format!("_{local}")).collect();
+            let locals: Vec<_> = locals.collect();
+            let declaration = format!(
+                "const[{}]=_di([{}],{name});",
+                bindings.join(","),
+                locals.join(",")
+            );
+            assert!(output.contains(&declaration), "{name}");
+            assert!(report.skip_reason.is_none());
+        }
+    }
+
+    #[test]
+    fn test_should_report_skip_reasons() {
+        let (reports, output) = analyze_and_transform(
+            r#"
+import { Modal } from './modal';
+import { RENDER } from './keys';
+class MyComponent extends React.Component {
+    [RENDER]() {
+        return <Modal />;
+    }
+}
+"#,
+        );
+
+        let skipped: Vec<_> = reports
+            .iter()
+            .map(|report| (report.name.clone(), report.skip_reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            [("MyComponent".into(), Some(SkipReason::ComputedMethodKey))]
+        );
+        assert!(reports.iter().all(|report| report.dependencies.is_empty()));
+        assert!(!output.contains("_di("));
+    }
+
+    #[test]
+    fn test_should_only_inject_allowed_scopes() {
+        let input = r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+function useModal() {
+    return Modal;
+}
+class Legacy extends React.Component {
+    render() {
+        return <Modal />;
+    }
+}
+const renderModal = () => {
+    return <Modal />;
+};"#;
+        let injected_scopes = |scopes: Vec<ScopeKind>| {
+            let config = TransformConfig {
+                scopes,
+                ..Default::default()
+            };
+            let output = transform(TransformVisitor::with_config(config), input);
+            ["MyComponent", "useModal", "Legacy", "renderModal"]
+                .into_iter()
+                .filter(|scope| output.contains(&format!("], {scope});")))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            injected_scopes(TransformConfig::default().scopes),
+            ["MyComponent", "Legacy"]
+        );
+        assert_eq!(injected_scopes(vec![ScopeKind::Component]), ["MyComponent"]);
+        assert_eq!(injected_scopes(vec![ScopeKind::Hook]), ["useModal"]);
+        assert_eq!(injected_scopes(vec![ScopeKind::Class]), ["Legacy"]);
+        assert_eq!(injected_scopes(vec![ScopeKind::Function]), ["renderModal"]);
+        assert!(injected_scopes(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_should_work_with_lazy_imported_directly() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { lazy } from 'react';
+import React from 'react';
+import { loadChunk } from './chunks';
+const Modal = lazy(() => {
+    return loadChunk('modal');
+});
+const Dialog = React.lazy(() => {
+    return loadChunk('dialog');
+});
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { lazy } from 'react';
+import React from 'react';
+import { loadChunk } from './chunks';
+const Modal = lazy(() => {
+    const [_loadChunk] = _di([loadChunk], Modal);
+    return _loadChunk('modal');
+});
+const Dialog = React.lazy(() => {
+    const [_loadChunk] = _di([loadChunk], Dialog);
+    return _loadChunk('dialog');
+});
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_exclude_react_default_import() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Suspense } from 'react';
+import * as ReactAll from 'react';
+function MyComponent() {
+    const [open] = React.useState(false);
+    const ref = ReactAll.useRef();
+    return <Suspense>{open}</Suspense>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Suspense } from 'react';
+import * as ReactAll from 'react';
+function MyComponent() {
+    const [_Suspense] = _di([Suspense], MyComponent);
+    const [open] = React.useState(false);
+    const ref = ReactAll.useRef();
+    return <_Suspense>{open}</_Suspense>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_include_react_default_import_when_configured() {
+        let config = TransformConfig {
+            exclude_react_default: false,
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import React from 'react';
+function MyComponent() {
+    const [open] = React.useState(false);
+    return <div>{open}</div>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React from 'react';
+function MyComponent() {
+    const [_React] = _di([React], MyComponent);
+    const [open] = _React.useState(false);
+    return <div>{open}</div>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_rename_registration_call_arguments() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { di, injectable } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    const deps = [di(Modal), injectable(Modal, () => null)];
+    return <Modal deps={deps} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di, injectable } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    const [_Modal] = di([Modal], MyComponent);
+    const deps = [di(Modal), injectable(Modal, () => null)];
+    return <_Modal deps={deps} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_memo_imported_directly() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { memo as pure } from 'react';
+import { Modal } from './modal';
+const MyComponent = pure(() => {
+    return <Modal />;
+});
+const Other = React.memo(function Other() {
+    return <Modal />;
+});
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { memo as pure } from 'react';
+import { Modal } from './modal';
+const MyComponent = pure(() => {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+});
+const Other = React.memo(function Other() {
+    const [_Modal] = _di([Modal], Other);
+    return <_Modal />;
+});
+"#,
+        );
+    }
+
+    /// Decorators are evaluated once when the class is defined, outside of any render, so
+    /// imports referenced in their arguments stay module-level references. Methods of the
+    /// decorated class are still injected.
+    #[test]
+    fn test_should_work_with_class_decorator_arguments() {
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                decorators: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { connect } from './store';
+import { Store } from './store';
+import { Modal } from './modal';
+@connect(Store)
+class MyComponent extends React.Component<Props> {
+    render() {
+        return <Modal store={Store} />;
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { connect } from './store';
+import { Store } from './store';
+import { Modal } from './modal';
+@connect(Store)
+class MyComponent extends React.Component<Props> {
+    render() {
+        const [_Modal, _Store] = _di([Modal, Store], MyComponent);
+        return <_Modal store={_Store} />;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_tagged_template_literals() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { gql } from 'graphql-tag';
+import { html } from 'lit';
+import { Modal, USER_FIELDS } from './modal';
+function MyComponent() {
+    const query = gql`query { user { ${USER_FIELDS} } }`;
+    return html`<div>${Modal}</div>`;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { gql } from 'graphql-tag';
+import { html } from 'lit';
+import { Modal, USER_FIELDS } from './modal';
+function MyComponent() {
+    const [_gql, _USER_FIELDS, _html, _Modal] = _di([gql, USER_FIELDS, html, Modal], MyComponent);
+    const query = _gql`query { user { ${_USER_FIELDS} } }`;
+    return _html`<div>${_Modal}</div>`;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_repeated_references_once() {
+        let output = transform(
+            TransformVisitor::default(),
+            r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <div>
+        <Modal />
+        <Modal />
+        <Modal.Header />
+        <Modal.Footer>{Modal.displayName}</Modal.Footer>
+    </div>;
+}"#,
+        );
+
+        assert_eq!(output.matches("const [_Modal]").count(), 1);
+        assert_eq!(output.matches("_di(").count(), 1);
+        assert!(!output.contains("<Modal"));
+    }
+
+    #[test]
+    fn test_should_transform_barrel_heavy_stress_fixture() {
+        const ICONS: usize = 120;
+        const COMPONENTS: usize = 20;
+        let package = |icon: usize| format!("@corp/icons-{}", icon % 5);
+
+        let mut input = String::from("import * as UI from '@corp/ui';\n");
+        for icon in 0..ICONS {
+            input += &format!("import {{ Icon{icon} }} from '{}';\n", package(icon));
+        }
+        let mut uses = vec![];
+        for component in 0..COMPONENTS {
+            let icons: Vec<_> = (0..12).map(|k| (component * 7 + k * 13) % ICONS).collect();
+            let children: String = icons
+                .iter()
+                .map(|icon| format!("<Icon{icon} /><Icon{icon} />"))
+                .collect();
+            input += &format!(
+                "function Component{component}() {{\n    return <UI.Box>{children}</UI.Box>;\n}}\n"
+            );
+            uses.push(icons);
+        }
+
+        let config = TransformConfig {
+            group_by: GroupBy::Package,
+            ..Default::default()
+        };
+        let output: String = transform(TransformVisitor::with_config(config), &input)
+            .split_whitespace()
+            .collect();
+
+        for (component, icons) in uses.iter().enumerate() {
+            // `UI.Box` is referenced first, then every icon package in order of first use
+            let mut groups: Vec<(String, Vec<usize>)> = vec![];
+            for &icon in icons {
+                match groups.iter_mut().find(|(name, _)| *name == package(icon)) {
+                    Some((_, group)) if !group.contains(&icon) => group.push(icon),
+                    Some(_) => {}
+                    None => groups.push((package(icon), vec![icon])),
+                }
+            }
+            let mut expected = format!(
+                "functionComponent{component}(){{const[_UI_Box]=_di([UI.Box],Component{component});"
+            );
+            for (_, group) in groups {
+                let bindings: Vec<_> = group.iter().map(|icon| format!("_Icon{icon}")).collect();
+                let dependencies: Vec<_> = group.iter().map(|icon| format!("Icon{icon}")).collect();
+                expected += &format!(
+                    "const[{}]=_di([{}],Component{component});",
+                    bindings.join(","),
+                    dependencies.join(",")
+                );
+            }
+            expected += "return<_UI_Box>";
+            assert!(output.contains(&expected), "Component{component}");
+        }
+        assert_eq!(output.matches("=_di(").count(), COMPONENTS * 6);
+    }
+
+    #[test]
+    fn test_should_leave_production_env_untouched() {
+        let input = r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}"#;
+        let production = transform(TransformVisitor::default().with_env("production"), input);
+        assert_eq!(production, print(input));
+
+        let test = transform(TransformVisitor::default().with_env("test"), input);
+        assert!(test.contains("_di(["));
+
+        let config = TransformConfig {
+            enabled_envs: vec!["production".into()],
+            ..Default::default()
+        };
+        let opted_in = transform(
+            TransformVisitor::with_config(config).with_env("production"),
+            input,
+        );
+        assert!(opted_in.contains("_di(["));
+    }
+
+    #[test]
+    fn test_should_alias_existing_di_import_with_force_fn_name() {
+        let config = TransformConfig {
+            force_fn_name: true,
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { di as injectDi } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as injectDi, di as _di } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_exclude_scopes() {
+        let config = TransformConfig {
+            exclude_scopes: vec!["^App$".into(), ".*Provider$".into()],
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::with_config(config.clone())),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { ThemeContext } from './theme';
+function ThemeProvider({ children }) {
+    const render = function () {
+        return <ThemeContext.Provider>{children}</ThemeContext.Provider>;
+    };
+    return render();
+}
+const App = () => {
+    return <ThemeProvider><Modal /></ThemeProvider>;
+};
+function AppShell() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { ThemeContext } from './theme';
+function ThemeProvider({ children }) {
+    const render = function () {
+        return <ThemeContext.Provider>{children}</ThemeContext.Provider>;
+    };
+    return render();
+}
+const App = () => {
+    return <ThemeProvider><Modal /></ThemeProvider>;
+};
+function AppShell() {
+    const [_Modal] = _di([Modal], AppShell);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_report_excluded_scopes() {
+        let config = TransformConfig {
+            exclude_scopes: vec![".*Provider$".into()],
+            ..Default::default()
+        };
+        let reports = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let input = r#"
+import { ThemeContext } from './theme';
+function ThemeProvider({ children }) {
+    const render = function () {
+        return <ThemeContext.Provider>{children}</ThemeContext.Provider>;
+    };
+    return render();
+}"#;
+            let module = tester.with_parser("input.js", syntax, input, |p| p.parse_module())?;
+            Ok(analyze_dependencies(&Program::Module(module), &config).unwrap())
+        });
+
+        let skipped: Vec<_> = reports
+            .iter()
+            .map(|report| (report.name.clone(), report.skip_reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            [("ThemeProvider".into(), Some(SkipReason::ExcludedScope))]
+        );
+    }
+
+    #[test]
+    fn test_should_add_di_import_when_missing() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+function Other() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function MyComponent() {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+}
+function Other() {
+    const [_Modal] = _di([Modal], Other);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_reuse_existing_di_import() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { di } from 'react-magnetic-di';
+function MyComponent() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { Modal } from './modal';
+import { di } from 'react-magnetic-di';
+function MyComponent() {
+    const [_Modal] = di([Modal], MyComponent);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_reuse_aliased_di_import() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { di as injectDi } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as injectDi } from 'react-magnetic-di';
+import { Modal } from './modal';
+function MyComponent() {
+    const [_Modal] = injectDi([Modal], MyComponent);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_add_di_import_without_injections() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export { Modal };
+function MyComponent() {
+    return <div />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { Modal } from './modal';
+export { Modal };
+function MyComponent() {
+    return <div />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_switch_discriminants() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Mode } from './mode';
+function MyComponent() {
+    switch (Mode.current) {
+        case 'edit':
+            return <input />;
+        default:
+            return null;
+    }
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Mode } from './mode';
+function MyComponent() {
+    const [_Mode] = _di([Mode], MyComponent);
+    switch (_Mode.current) {
+        case 'edit':
+            return <input />;
+        default:
+            return null;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_create_visitor_from_config() {
+        let config = TransformConfig {
+            prefix: "__".into(),
+            ..Default::default()
+        };
+        let output = transform(
+            config.into(),
+            r#"
+import { Modal } from './modal';
+function MyComponent() {
+    return <Modal />;
+}
+"#,
+        );
+
+        assert!(output.contains("<__Modal/>"));
+    }
+
+    #[test]
+    fn test_should_not_inject_array_literals_at_module_scope() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal, Button } from './ui';
+const components = [Modal, Button];
+export const rendered = [Modal, Button].map(Comp => <Comp key={Comp.name} />);
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { Modal, Button } from './ui';
+const components = [Modal, Button];
+export const rendered = [Modal, Button].map(Comp => <Comp key={Comp.name} />);
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_array_literals_in_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { useMemo } from 'react';
+import { Icon1, Icon2 } from './icons';
+function Toolbar() {
+    const icons = useMemo(() => [Icon1, Icon2], []);
+    return icons.map(Icon => <Icon />);
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { useMemo } from 'react';
+import { Icon1, Icon2 } from './icons';
+function Toolbar() {
+    const [_useMemo, _Icon1, _Icon2] = _di([useMemo, Icon1, Icon2], Toolbar);
+    const icons = _useMemo(() => [_Icon1, _Icon2], []);
+    return icons.map(Icon => <Icon />);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_components_inside_namespaces() {
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Table, Row } from './table';
+namespace Admin {
+    export const UserTable = () => {
+        return <Table />;
+    };
+    export namespace Users {
+        export function UserRow() {
+            return <Row />;
+        }
+    }
+}
+declare namespace Legacy {
+    const LegacyTable: typeof Table;
+    function LegacyRow(): typeof Row;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Table, Row } from './table';
+namespace Admin {
+    export const UserTable = () => {
+        const [_Table] = _di([Table], UserTable);
+        return <_Table />;
+    };
+    export namespace Users {
+        export function UserRow() {
+            const [_Row] = _di([Row], UserRow);
+            return <_Row />;
+        }
+    }
+}
+declare namespace Legacy {
+    const LegacyTable: typeof Table;
+    function LegacyRow(): typeof Row;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_import_used_as_tag_and_expression_once() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function MyComponent({ open }) {
+    return <div>
+        <Modal />
+        <button onClick={() => open(Modal)} />
+    </div>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function MyComponent({ open }) {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <div>
+        <_Modal />
+        <button onClick={() => open(_Modal)} />
+    </div>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_only_inject_capitalized_scopes_by_default() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { format } from 'date-fns';
+import { useQuery } from './query';
+function helper(date) {
+    return format(date, 'yyyy');
+}
+function useThing() {
+    return useQuery();
+}
+class registry {
+    get() {
+        return useQuery();
+    }
+}
+function Thing() {
+    return useQuery();
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { format } from 'date-fns';
+import { useQuery } from './query';
+function helper(date) {
+    return format(date, 'yyyy');
+}
+function useThing() {
+    return useQuery();
+}
+class registry {
+    get() {
+        return useQuery();
+    }
+}
+function Thing() {
+    const [_useQuery] = _di([useQuery], Thing);
+    return _useQuery();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_call_result_spread_into_jsx_props() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { Config, getProps } from './config';
+function MyComponent() {
+    return <Modal {...getProps(Config)} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { Config, getProps } from './config';
+function MyComponent() {
+    const [_Modal, _getProps, _Config] = _di([Modal, getProps, Config], MyComponent);
+    return <_Modal {..._getProps(_Config)} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_group_by_none() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    group_by: GroupBy::None,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { useQuery } from './query';
+function MyComponent() {
+    const data = useQuery();
+    return <Modal data={data} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { useQuery } from './query';
+function MyComponent() {
+    const [_useQuery] = _di([useQuery], MyComponent);
+    const [_Modal] = _di([Modal], MyComponent);
+    const data = _useQuery();
+    return <_Modal data={data} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_unshadow_component_name_shadowed_by_local_function() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function C() {
+    const C = function C() {};
+    return <Modal onClose={C} />;
+}
+function Other() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function C() {
+    const [_Modal] = _di([Modal], C);
+    const _C = function C() {};
+    return <_Modal onClose={_C} />;
+}
+function Other() {
+    const [_Modal] = _di([Modal], Other);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_apply_name_template() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    name_template: Some("{name}Di".into()),
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { useQuery } from './query';
+function MyComponent() {
+    const data = useQuery();
+    return <Modal data={data} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { useQuery } from './query';
+function MyComponent() {
+    const [useQueryDi, ModalDi] = _di([useQuery, Modal], MyComponent);
+    const data = useQueryDi();
+    return <ModalDi data={data} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_reject_invalid_name_templates() {
+        assert!(is_valid_name_template("{name}Di"));
+        assert!(is_valid_name_template("$mock_{name}"));
+        assert!(!is_valid_name_template("{name}-di"));
+        assert!(!is_valid_name_template("1{name}"));
+        assert!(!is_valid_name_template("mocked"));
+        assert!(!is_valid_name_template(""));
+        assert!(!is_valid_name_template("{name}"));
+    }
+
+    #[test]
+    fn test_should_reject_invalid_prefixes_and_fn_names() {
+        let config = |prefix: &str, fn_name: &str| TransformConfig {
+            prefix: prefix.into(),
+            fn_name: fn_name.into(),
+            ..Default::default()
+        };
+        assert!(validate_names(&config("_", "_di")).is_ok());
+        assert!(validate_names(&config("$", "injectDi")).is_ok());
+        assert!(validate_names(&config("", "_di")).is_err());
+        assert!(validate_names(&config("-", "_di")).is_err());
+        assert!(validate_names(&config("_", "my-di")).is_err());
+        assert!(validate_names(&config("_", "")).is_err());
+        // The prefix is unused with a name template
+        let config = TransformConfig {
+            name_template: Some("{name}Di".into()),
+            ..config("", "_di")
+        };
+        assert!(validate_names(&config).is_ok());
+    }
+
+    /// Run `analyze_dependencies` over a JSX module importing `Modal`.
+    fn analyze_with(config: TransformConfig) -> Result<Vec<ComponentDeps>, ConfigError> {
+        Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let input = "import { Modal } from 'modal';\nconst Card = () => <Modal />;";
+            let module = tester.with_parser("input.js", syntax, input, |p| p.parse_module())?;
+            Ok(analyze_dependencies(&Program::Module(module), &config))
+        })
+    }
+
+    #[test]
+    fn test_should_reject_configs_naming_bindings_after_the_import() {
+        let result = analyze_with(TransformConfig {
+            name_template: Some("{name}".into()),
+            ..Default::default()
+        });
+        assert!(
+            matches!(result, Err(ConfigError::InvalidNameTemplate(template)) if template == "{name}")
+        );
+    }
+
+    #[test]
+    fn test_should_return_config_errors_rather_than_panic() {
+        let result = analyze_with(TransformConfig {
+            exclude_scopes: vec!["(".into()],
+            ..Default::default()
+        });
+        assert!(
+            matches!(result, Err(ConfigError::InvalidExcludeScope { pattern, .. }) if pattern == "(")
+        );
+        let result = analyze_with(TransformConfig {
+            redirects: [("modal#Modal".into(), "modal-mock".into())].into(),
+            ..Default::default()
+        });
+        assert!(
+            matches!(result, Err(ConfigError::InvalidRedirectTarget(target)) if target == "modal-mock")
+        );
+        assert!(analyze_with(TransformConfig::default()).is_ok());
+        assert!(matches!(
+            TransformConfig::from_json(r#"{ "prefix": 1 }"#),
+            Err(ConfigError::InvalidJson(_))
+        ));
+        assert!(TransformConfig::from_json(r#"{ "prefix": "$" }"#).is_ok());
+    }
+
+    #[test]
+    fn test_should_work_in_forward_ref_and_memo_wrapped_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { memo } from 'react';
+import { Modal } from './modal';
+export const Input = React.forwardRef((props, ref) => {
+    return <Modal ref={ref} {...props} />;
+});
+export const Button = memo(function Button(props) {
+    return <Modal {...props} />;
+});
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { memo } from 'react';
+import { Modal } from './modal';
+export const Input = React.forwardRef((props, ref) => {
+    const [_Modal] = _di([Modal], Input);
+    return <_Modal ref={ref} {...props} />;
+});
+export const Button = memo(function Button(props) {
+    const [_Modal] = _di([Modal], Button);
+    return <_Modal {...props} />;
+});
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_destructured_object_of_components() {
+        // Each function of the object literal is injected as the component it's destructured to
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { Button } from './button';
+export const { A, b: B, C, ...rest } = {
+    A: () => {
+        return <Modal />;
+    },
+    b() {
+        return <Button />;
+    },
+    C: <Modal />,
+    D: () => {
+        return <Button />;
+    },
+};
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { Button } from './button';
+export const { A, b: B, C, ...rest } = {
+    A: () => {
+        const [_Modal] = _di([Modal], A);
+        return <_Modal />;
+    },
+    b() {
+        const [_Button] = _di([Button], B);
+        return <_Button />;
+    },
+    C: <Modal />,
+    D: () => {
+        return <Button />;
+    },
+};
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_leave_declaration_files_alone() {
+        let input = r#"
+import { Modal } from './modal';
+export declare function Card(): JSX.Element;
+export function Dialog() {
+    return Modal;
+}
+"#;
+        for filename in ["src/card.d.ts", "src/card.d.mts", "src/card.d.cts"] {
+            test_inline_input_output(
+                Syntax::Typescript(Default::default()),
+                |_| TransformPlugin(TransformVisitor::default().with_filename(filename)),
+                input,
+                input,
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_treat_ambient_only_files_as_declaration_files() {
+        let is_declaration_file = |input: &str| {
+            Tester::run(|tester| {
+                let syntax = Syntax::Typescript(Default::default());
+                let module = tester
+                    .with_parser("input.ts", syntax, input, |parser| parser.parse_module())?;
+                Ok(super::is_declaration_file(
+                    &Program::Module(module),
+                    Some("input.ts"),
+                ))
+            })
+        };
+
+        assert!(is_declaration_file(
+            r#"
+import type { Props } from './props';
+import { Modal } from './modal';
+export declare const Card: typeof Modal;
+declare function Dialog(props: Props): JSX.Element;
+export interface CardProps extends Props {}
+declare namespace Legacy {
+    const Table: typeof Modal;
+}
+"#
+        ));
+        assert!(!is_declaration_file(
+            r#"
+import { Modal } from './modal';
+declare const Card: typeof Modal;
+export const Dialog = () => Modal;
+"#
+        ));
+    }
+
+    #[test]
+    fn test_should_name_anonymous_default_exports_after_the_file() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::default().with_filename("src/components/user-card.jsx"))
+            },
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export default () => {
+    return <Modal />;
+};
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+const UserCard = () => {
+    const [_Modal] = _di([Modal], UserCard);
+    return <_Modal />;
+};
+export default UserCard;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_name_anonymous_default_export_functions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+const DefaultExport = 'taken';
+export default function () {
+    return <Modal title={DefaultExport} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+const DefaultExport = 'taken';
+export default function DefaultExport2() {
+    const [_Modal] = _di([Modal], DefaultExport2);
+    return <_Modal title={DefaultExport} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_name_anonymous_wrapped_default_exports() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default().with_filename("src/Profile.jsx")),
+            // Input codes
+            r#"
+import { memo } from 'react';
+import { Modal } from './modal';
+export default memo(() => {
+    return <Modal />;
+});
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { memo } from 'react';
+import { Modal } from './modal';
+const Profile = memo(() => {
+    const [_Modal] = _di([Modal], Profile);
+    return <_Modal />;
+});
+export default Profile;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_change_anonymous_default_exports_without_imports() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default().with_filename("src/Empty.jsx")),
+            // Input codes
+            r#"
+export default function () {
+    return <div />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+export default function () {
+    return <div />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_name_anonymous_default_exports_with_nothing_injected() {
+        let visitor = TransformVisitor::with_config(TransformConfig {
+            ignore: vec!["Modal".into()],
+            ..Default::default()
+        });
+        let output = transform(
+            visitor.with_filename("src/Card.jsx"),
+            r#"
+import { Modal } from './modal';
+export default () => <Modal />;"#,
+        );
+        assert!(output.contains("export default ()=><Modal/>;"));
+        assert!(!output.contains("Card"));
+
+        let config = TransformConfig {
+            exclude_scopes: vec!["^Card$".into()],
+            ..Default::default()
+        };
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(
+                    TransformVisitor::with_config(config.clone()).with_filename("src/Card.jsx"),
+                )
+            },
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export default function () {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { Modal } from './modal';
+export default function () {
+    return <Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_name_parenthesized_default_exports() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default().with_filename("src/Card.jsx")),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export default (() => <Modal />);
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+const Card = () => {
+    const [_Modal] = _di([Modal], Card);
+    return <_Modal />;
+};
+export default Card;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_analyze_repeated_import_declarations_once() {
+        let locals = Tester::run(|tester| {
+            let module = tester.with_parser(
+                "input.js",
+                Syntax::Es(Default::default()),
+                r#"
+import { Modal } from './modal';
+import { Modal } from './modal';
+import { Dialog } from './modal';
+import Button, * as buttons from './button';
+import Button, * as buttons from './button';
+"#,
+                |parser| parser.parse_module(),
+            )?;
+            let mut analysis = ImportAnalysis::new();
+            module.visit_with(&mut analysis);
+            Ok(analysis
+                .into_import_specifications()
+                .into_iter()
+                .map(|import| import.local_imported_symbol.to_string())
+                .collect::<Vec<_>>())
+        });
+
+        assert_eq!(locals, ["Modal", "Dialog", "Button", "buttons"]);
+    }
+
+    #[test]
+    fn test_should_avoid_reserved_names() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    reserved_names: vec!["_Modal".into(), "__Modal".into(), "_Other".into()],
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { Button } from './button';
+function MyComponent() {
+    return <Modal><Button /></Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { Button } from './button';
+function MyComponent() {
+    const [___Modal, _Button] = _di([Modal, Button], MyComponent);
+    return <___Modal><_Button /></___Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_expression_bodied_arrow_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal, Spinner } from './ui';
+const MyComponent = () => <Modal />;
+const Loader = ({ loading }) => loading ? <Spinner /> : <Modal />;
+const Empty = () => <div />;
+const Shadowed = ({ Modal }) => <Modal />;
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal, Spinner } from './ui';
+const MyComponent = () => {
+    const [_Modal] = _di([Modal], MyComponent);
+    return <_Modal />;
+};
+const Loader = ({ loading }) => {
+    const [_Spinner, _Modal] = _di([Spinner, Modal], Loader);
+    return loading ? <_Spinner /> : <_Modal />;
+};
+const Empty = () => <div />;
+const Shadowed = ({ Modal }) => <Modal />;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_parenthesized_expression_bodied_arrow_components() {
+        let output = transform(
+            TransformVisitor::default(),
+            r#"
+import { Modal } from './modal';
+const MyComponent = () => (
+    <Modal />
+);
+"#,
+        );
+
+        assert!(output.contains("const [_Modal] = _di(["));
+        assert!(output.contains("return (<_Modal/>);"));
+    }
+
+    #[test]
+    fn test_should_only_inject_allowed_packages() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    packages: Some(vec!["modal".into()]),
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import Modal from 'modal';
+import { Header } from 'modal/header';
+import { Button } from 'other';
+import { Dialog } from 'modal-extra';
+function MyComponent() {
+    return <Modal><Header /><Button /><Dialog /></Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import Modal from 'modal';
+import { Header } from 'modal/header';
+import { Button } from 'other';
+import { Dialog } from 'modal-extra';
+function MyComponent() {
+    const [_Modal, _Header] = _di([Modal, Header], MyComponent);
+    return <_Modal><_Header /><Button /><Dialog /></_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_styled_component_interpolations() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import styled from 'styled-components';
+import { Color, spacing } from './theme';
+const Box = styled.div`
+    color: ${theme => theme[Color]};
+    padding: ${spacing}px;
+`;
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import styled from 'styled-components';
+import { Color, spacing } from './theme';
+const Box = styled.div`
+    color: ${theme => {
+        const [_Color] = _di([Color], Box);
+        return theme[_Color];
+    }};
+    padding: ${spacing}px;
+`;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_keep_imports_in_parameter_defaults() {
+        // Hoisting calls out of parameter defaults would change the order hooks run in, so those
+        // references are left alone with a warning
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { useDefaultData } from './data';
+import { Modal } from './modal';
+function Panel({ data = useDefaultData() } = {}) {
+    return <Modal data={data} fallback={useDefaultData} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { useDefaultData } from './data';
+import { Modal } from './modal';
+function Panel({ data = useDefaultData() } = {}) {
+    const [_Modal, _useDefaultData] = _di([Modal, useDefaultData], Panel);
+    return <_Modal data={data} fallback={_useDefaultData} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_keep_replacing_after_nested_helper() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Row } from './row';
+import { Modal } from './modal';
+function List({ items }) {
+    const rows = items.map(renderRow);
+    function renderRow(item) {
+        return <Row item={item} />;
+    }
+    return <Modal>{rows}</Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Row } from './row';
+import { Modal } from './modal';
+function List({ items }) {
+    const [_Row, _Modal] = _di([Row, Modal], List);
+    const rows = items.map(renderRow);
+    function renderRow(item) {
+        return <_Row item={item} />;
+    }
+    return <_Modal>{rows}</_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_components_defined_inside_factories() {
+        // `createCard` isn't a component itself, the components it defines are scoped by their
+        // own names
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+import { Button } from './button';
+function createCard(title) {
+    const Card = () => {
+        return <Modal title={title} />;
+    };
+    function Footer() {
+        return <Button />;
+    }
+    return [Card, Footer];
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+import { Button } from './button';
+function createCard(title) {
+    const Card = () => {
+        const [_Modal] = _di([Modal], Card);
+        return <_Modal title={title} />;
+    };
+    function Footer() {
+        const [_Button] = _di([Button], Footer);
+        return <_Button />;
+    }
+    return [Card, Footer];
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_inject_ignored_names() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    ignore: vec!["classnames".into()],
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import classnames from 'classnames';
+import { Modal } from './modal';
+function Dialog({ open }) {
+    return <Modal className={classnames({ open })} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import classnames from 'classnames';
+import { Modal } from './modal';
+function Dialog({ open }) {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal className={classnames({ open })} />;
+}
+"#,
+        );
+    }
+
+    /// Names `module` exports, `default` included.
+    fn exported_names(module: &Module) -> Vec<String> {
+        let export_name = |name: &ModuleExportName| match name {
+            ModuleExportName::Ident(ident) => ident.sym.to_string(),
+            ModuleExportName::Str(str) => str.value.to_string(),
+        };
+        let mut names = vec![];
+        for decl in module.body.iter().filter_map(|item| item.as_module_decl()) {
+            match decl {
+                ModuleDecl::ExportDecl(export) => match &export.decl {
+                    Decl::Fn(fn_decl) => names.push(fn_decl.ident.sym.to_string()),
+                    Decl::Class(class) => names.push(class.ident.sym.to_string()),
+                    Decl::Var(var) => names.extend(
+                        var.decls
+                            .iter()
+                            .filter_map(|declarator| declarator.name.as_ident())
+                            .map(|ident| ident.sym.to_string()),
+                    ),
+                    _ => {}
+                },
+                ModuleDecl::ExportNamed(named) => names.extend(
+                    named
+                        .specifiers
+                        .iter()
+                        .filter_map(|specifier| specifier.as_named())
+                        .map(|specifier| {
+                            export_name(specifier.exported.as_ref().unwrap_or(&specifier.orig))
+                        }),
+                ),
+                ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+                    names.push("default".into())
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+
+    /// Whether Fast Refresh would hot update a module exporting `names` in place, which it only
+    /// does if every export is a component or hook.
+    fn is_refresh_boundary(names: &[String]) -> bool {
+        names
+            .iter()
+            .all(|name| name == "default" || function_scope_kind(name) != ScopeKind::Function)
+    }
+
+    /// Exports of `input` before and after running `visitor` over it, and the transformed code.
+    fn transform_exports(
+        visitor: TransformVisitor,
+        input: &str,
+    ) -> (Vec<String>, Vec<String>, String) {
+        Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let before =
+                tester.with_parser("input.js", syntax, input, |parser| parser.parse_module())?;
+            let after = tester.apply_transform(as_folder(visitor), "input.js", syntax, input)?;
+            let code = tester.print(&after, &tester.comments.clone());
+            Ok((exported_names(&before), exported_names(&after), code))
+        })
+    }
+
+    #[test]
+    fn test_should_not_change_refresh_boundaries() {
+        let (before, after, code) = transform_exports(
+            TransformVisitor::default(),
+            r#"
+'use client';
+import { Modal } from './modal';
+import { useQuery } from './query';
+export function Card() {
+    return <Modal />;
+}
+export const useCard = () => useQuery();
+const Footer = () => <Modal />;
+export { Footer as CardFooter };
+export default function () {
+    return <Card />;
+}
+"#,
+        );
+        assert_eq!(before, after);
+        assert!(is_refresh_boundary(&after));
+        // Directives only take effect at the very top of the module
+        assert!(code
+            .trim_start()
+            .starts_with("'use client';\nimport { di as _di }"));
+    }
+
+    #[test]
+    fn test_should_keep_export_of_anonymous_default_export() {
+        let (before, after, code) = transform_exports(
+            TransformVisitor::default(),
+            r#"
+"use client";
+"use strict";
+import { Modal } from './modal';
+export const formatTitle = (title) => title.trim();
+export default () => <Modal />;
+"#,
+        );
+        assert_eq!(before, after);
+        assert!(!is_refresh_boundary(&after));
+        assert!(code.contains("\"use strict\";\nimport { di as _di }"));
+        assert!(code.contains("export default DefaultExport;"));
+    }
+
+    #[test]
+    fn test_should_work_in_class_field_arrows() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import Modal from 'modal';
+import Button from 'button';
+
+class MyComponent extends Component {
+    static Footer = () => <Button />;
+    renderHeader = () => {
+        return <Modal />;
+    };
+    render() {
+        return <Button>{this.renderHeader()}</Button>;
+    }
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import Modal from 'modal';
+import Button from 'button';
+
+class MyComponent extends Component {
+    static Footer = () => {
+        const [_Button] = _di([Button], MyComponent);
+        return <_Button />;
+    };
+    renderHeader = () => {
+        const [_Modal] = _di([Modal], MyComponent);
+        return <_Modal />;
+    };
+    render() {
+        const [_Button] = _di([Button], MyComponent);
+        return <_Button>{this.renderHeader()}</_Button>;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_import_shared_by_ternary_branches_once() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function Dialog({ compact }) {
+    return compact ? <Modal size="small" /> : <Modal size="large" />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function Dialog({ compact }) {
+    const [_Modal] = _di([Modal], Dialog);
+    return compact ? <_Modal size="small" /> : <_Modal size="large" />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_computed_destructuring_keys() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal, MODAL_KEY } from './modal';
+function Dialog({ slots }) {
+    const { [MODAL_KEY]: { [MODAL_KEY]: title } = {}, ...rest } = slots;
+    return <Modal title={title} {...rest} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal, MODAL_KEY } from './modal';
+function Dialog({ slots }) {
+    const [_MODAL_KEY, _Modal] = _di([MODAL_KEY, Modal], Dialog);
+    const { [_MODAL_KEY]: { [_MODAL_KEY]: title } = {}, ...rest } = slots;
+    return <_Modal title={title} {...rest} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_give_injected_statements_the_body_span() {
+        Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let input = r#"
+import { Modal } from './modal';
+function Dialog() {
+    return <Modal />;
+}
+"#;
+            let module = tester.apply_transform(
+                as_folder(TransformVisitor::default()),
+                "input.js",
+                syntax,
+                input,
+            )?;
+            let function = module
+                .body
+                .iter()
+                .find_map(|item| item.as_stmt()?.as_decl()?.as_fn_decl())
+                .expect("no function in the output");
+            let body = function.function.body.as_ref().unwrap();
+            let declaration = &body.stmts[0];
+            assert!(declaration.is_decl());
+            assert!(!declaration.span().is_dummy());
+            assert_eq!(declaration.span(), body.span);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_should_inject_static_components() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    static_components: true,
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import React, { Component } from 'react';
+import { Shimmer, Empty, Row, formatRows } from './table';
+
+class Table extends Component {
+    static Skeleton = () => <Shimmer />;
+    static renderEmpty() {
+        return <Empty />;
+    }
+    static format = (rows) => formatRows(rows);
+    render() {
+        return <Row />;
+    }
+}"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { Component } from 'react';
+import { Shimmer, Empty, Row, formatRows } from './table';
+
+class Table extends Component {
+    static Skeleton = () => {
+        const [_Shimmer] = _di([Shimmer], Table.Skeleton);
+        return <_Shimmer />;
+    };
+    static renderEmpty() {
+        const [_Empty] = _di([Empty], Table.renderEmpty);
+        return <_Empty />;
+    }
+    static format = (rows) => {
+        const [_formatRows] = _di([formatRows], Table);
+        return _formatRows(rows);
+    };
+    render() {
+        const [_Row] = _di([Row], Table);
+        return <_Row />;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_should_skip_files_without_components_when_prescanning() {
+        // The prescan only skips work, the output is the same either way
+        for input in [
+            r#"
+import { client } from './client';
+export const getUser = (id) => client.get(`/users/${id}`);
+"#,
+            r#"
+import { client } from './client';
+export const getUser = (id) => client.get(`/users/${id}`);
+export default () => client.get('/health');
+"#,
+            r#"
+import { client } from './client';
+export default function () { return client.get('/health'); }
+"#,
+        ] {
+            let prescanned = transform(TransformVisitor::default(), input);
+            let transformed = transform(
+                TransformVisitor::with_config(TransformConfig {
+                    prescan: false,
+                    ..Default::default()
+                }),
+                input,
+            );
+            assert_eq!(prescanned, transformed);
+        }
+    }
+
+    #[test]
+    fn test_should_not_inject_imports_used_only_as_types() {
+        test_inline_input_output(
+            Syntax::Typescript(TsSyntax {
+                tsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { useRef } from 'react';
+import { Modal, ModalProps } from './modal';
+function Dialog(props: ModalProps) {
+    const ref = useRef<Modal>(null);
+    const modal: Modal | null = ref.current as typeof Modal | null;
+    return <div ref={ref}>{modal}</div>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { useRef } from 'react';
+import { Modal, ModalProps } from './modal';
+function Dialog(props: ModalProps) {
+    const [_useRef] = _di([useRef], Dialog);
+    const ref = _useRef<Modal>(null);
+    const modal: Modal | null = ref.current as typeof Modal | null;
+    return <div ref={ref}>{modal}</div>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_not_change_components_with_every_import_filtered_out() {
+        let input = r#"
+import classnames from 'classnames';
+import { Modal } from './modal';
+function Dialog({ open }) {
+    return <Modal className={classnames({ open })} />;
+}
+"#;
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    packages: Some(vec!["@corp/ui".into()]),
+                    ignore: vec!["classnames".into()],
+                    emit_runtime_check: true,
+                    scope_setup_call: Some("__registerScope".into()),
+                    ..Default::default()
+                }))
+            },
+            input,
+            input,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_exported_function_declarations() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export function Dialog() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+export function Dialog() {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_exported_arrow_declarations() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export const Dialog = () => {
+    return <Modal />;
+};
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+export const Dialog = () => {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal />;
+};
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_named_default_export_functions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+export default function Dialog() {
+    return <Modal />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+export default function Dialog() {
+    const [_Modal] = _di([Modal], Dialog);
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_labels_and_loops() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Row, fetchRows, retry } from './rows';
+async function Table({ pages }) {
+    const rows = [];
+    retry: for (const page of pages) {
+        for (const item of page) {
+            if (!item) continue retry;
+            if (item.last) break retry;
+            rows.push(<Row item={item} />);
+        }
+    }
+    let result;
+    do {
+        result = await fetchRows();
+    } while (!result && retry());
+    for await (const chunk of fetchRows.stream()) {
+        rows.push(<Row item={chunk} />);
+    }
+    return rows;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Row, fetchRows, retry } from './rows';
+async function Table({ pages }) {
+    const [_Row, _fetchRows, _retry] = _di([Row, fetchRows, retry], Table);
+    const rows = [];
+    retry: for (const page of pages) {
+        for (const item of page) {
+            if (!item) continue retry;
+            if (item.last) break retry;
+            rows.push(<_Row item={item} />);
+        }
+    }
+    let result;
+    do {
+        result = await _fetchRows();
+    } while (!result && _retry());
+    for await (const chunk of _fetchRows.stream()) {
+        rows.push(<_Row item={chunk} />);
+    }
+    return rows;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_with_sequence_expressions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { track } from './analytics';
+import { Modal } from './modal';
+function Dialog() {
+    return (track('dialog'), <Modal />);
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { track } from './analytics';
+import { Modal } from './modal';
+function Dialog() {
+    const [_track, _Modal] = _di([track, Modal], Dialog);
+    return _track('dialog'), <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_namespace_import_members() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import * as Mui from 'mui';
+function Toolbar({ name }) {
+    const Icon = Mui[name];
+    return <Mui.Button icon={<Icon />} color={Mui.colors.primary}>{Object.keys(Mui)}</Mui.Button>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import * as Mui from 'mui';
+function Toolbar({ name }) {
+    const [_Mui, _Mui_Button, _Mui_colors] = _di([Mui, Mui.Button, Mui.colors], Toolbar);
+    const Icon = _Mui[name];
+    return <_Mui_Button icon={<Icon />} color={_Mui_colors.primary}>{Object.keys(_Mui)}</_Mui_Button>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_work_in_nested_wrappers() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    wrappers: vec!["observer".into()],
+                    ..Default::default()
+                }))
+            },
+            // Input codes
+            r#"
+import React, { memo, forwardRef } from 'react';
+import { observer } from 'mobx-react';
+import { Input } from './input';
+export const Fancy = memo(forwardRef((props, ref) => {
+    return <Input ref={ref} {...props} />;
+}));
+export const Field = observer(React.memo(function Field(props) {
+    return <Input {...props} />;
+}));
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import React, { memo, forwardRef } from 'react';
+import { observer } from 'mobx-react';
+import { Input } from './input';
+export const Fancy = memo(forwardRef((props, ref) => {
+    const [_Input] = _di([Input], Fancy);
+    return <_Input ref={ref} {...props} />;
+}));
+export const Field = observer(React.memo(function Field(props) {
+    const [_Input] = _di([Input], Field);
+    return <_Input {...props} />;
+}));
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_add_suppress_lint_comments() {
+        let output = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let visitor = TransformVisitor::with_config(TransformConfig {
+                suppress_lint: vec!["no-underscore-dangle".into(), "no-shadow".into()],
+                emit_runtime_check: true,
+                group_by: GroupBy::None,
+                ..Default::default()
+            })
+            .with_comments(tester.comments.clone());
+            let input = r#"
+import { Modal } from './modal';
+import { useQuery } from './query';
+function Dialog() {
+    const data = useQuery();
+    return <Modal data={data} />;
+}
+"#;
+            let module = tester.apply_transform(as_folder(visitor), "input.js", syntax, input)?;
+            Ok(tester.print(&module, &tester.comments.clone()))
+        });
+        let comment = "// eslint-disable-next-line no-underscore-dangle, no-shadow";
+        let lines: Vec<_> = output.lines().map(str::trim).collect();
+        let declarations = [
+            "if (typeof _di !== 'function') throw new Error('react-magnetic-di: `_di` is not \
+             defined, make sure the di helper is in scope');",
+            "const [_useQuery] = _di([",
+            "const [_Modal] = _di([",
+        ];
+        for declaration in declarations {
+            let index = lines.iter().position(|line| *line == declaration).unwrap();
+            assert_eq!(lines[index - 1], comment, "{output}");
+        }
+        assert_eq!(output.matches(comment).count(), declarations.len());
+    }
+
+    #[test]
+    fn test_should_not_suppress_lint_by_default() {
+        let output = Tester::run(|tester| {
+            let syntax = Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            });
+            let visitor = TransformVisitor::default().with_comments(tester.comments.clone());
+            let input = "import { Modal } from './modal';\nconst Dialog = () => <Modal />;";
+            let module = tester.apply_transform(as_folder(visitor), "input.js", syntax, input)?;
+            Ok(tester.print(&module, &tester.comments.clone()))
+        });
+        assert!(output.contains("const [_Modal] = _di(["));
+        assert!(!output.contains("eslint-disable"));
+    }
+
+    #[test]
+    fn test_should_share_the_component_scope_with_nested_named_functions() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import { Modal } from './modal';
+function Page() {
+    function Header() {
+        function helper() {
+            return <Modal />;
+        }
+        return helper();
+    }
+    return <Modal header={<Header />} />;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from './modal';
+function Page() {
+    const [_Modal] = _di([Modal], Page);
+    function Header() {
+        function helper() {
+            return <_Modal />;
+        }
+        return helper();
+    }
+    return <_Modal header={<Header />} />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_inject_first_member_of_deep_namespace_members() {
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            // Input codes
+            r#"
+import * as UI from 'ui-kit';
+function Signup() {
+    const onSubmit = () => UI.open(UI.Forms.validate);
+    return <UI.Modal>
+        <UI.Forms.Input onSubmit={onSubmit} />
+    </UI.Modal>;
+}
+"#,
+            // Output codes after transformed with plugin
+            r#"
+import { di as _di } from "react-magnetic-di";
+import * as UI from 'ui-kit';
+function Signup() {
+    const [_UI_open, _UI_Forms, _UI_Modal] = _di([UI.open, UI.Forms, UI.Modal], Signup);
+    const onSubmit = () => _UI_open(_UI_Forms.validate);
+    return <_UI_Modal>
+        <_UI_Forms.Input onSubmit={onSubmit} />
+    </_UI_Modal>;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_apply_mode() {
+        let input = r#"
+import { Modal } from 'ui';
+function Card() {
+    return <Modal />;
+}
+"#;
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| as_folder(TransformVisitor::default()),
+            input,
+            r#"
+import { di as _di } from "react-magnetic-di";
+import { Modal } from 'ui';
+function Card() {
+    const [_Modal] = _di([Modal], Card);
+    return <_Modal />;
+}
+"#,
+        );
+        test_inline_input_output(
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            |_| {
+                as_folder(TransformVisitor::with_config(TransformConfig {
+                    mode: Mode::Production,
+                    ..Default::default()
+                }))
+            },
+            input,
+            r#"
+import { Modal } from 'ui';
+function Card() {
+    const [_Modal] = [Modal];
+    return <_Modal />;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_should_analyze_imports() {
+        let imports = Tester::run(|tester| {
+            let input = r#"
+import Dialog from 'ui/dialog';
+import { Modal, Button as UIButton } from 'ui';
+import * as Icons from 'icons';
+import { type Props } from './types';
+"#;
+            let module = tester.with_parser(
+                "input.tsx",
+                Syntax::Typescript(TsSyntax {
+                    tsx: true,
+                    ..Default::default()
+                }),
+                input,
+                |p| p.parse_module(),
+            )?;
+            let mut analysis = ImportAnalysis::new();
+            module.visit_with(&mut analysis);
+            Ok(analysis.into_import_specifications())
+        });
+
+        let summary: Vec<_> = imports
+            .iter()
+            .map(|import| {
+                (
+                    &*import.local_imported_symbol,
+                    &*import.dependency_imported_symbol,
+                    &*import.package_name,
+                    import.kind,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Dialog", "Dialog", "ui/dialog", ImportKind::Default),
+                ("Modal", "Modal", "ui", ImportKind::Named),
+                ("UIButton", "Button", "ui", ImportKind::Named),
+                ("Icons", "Icons", "icons", ImportKind::Namespace),
+                ("Props", "Props", "./types", ImportKind::Named),
+            ]
+        );
+        assert!(imports
+            .iter()
+            .all(|import| import.symbol_id.0 == import.local_imported_symbol));
+        let type_only: Vec<_> = imports
+            .iter()
+            .filter(|import| import.is_type_only)
+            .map(|import| &*import.local_imported_symbol)
+            .collect();
+        assert_eq!(type_only, ["Props"]);
     }
 }
@@ -0,0 +1,81 @@
+use swc_core::ecma::ast::{
+    ArrowExpr, CallExpr, Callee, Expr, Function, MemberExpr, ModuleDecl, ModuleItem, Program,
+};
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// How a file imports its dependencies, which decides how imports added by the plugin are
+/// written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleSyntax {
+    /// `import { x } from 'pkg'`
+    Esm,
+    /// `const x = require('pkg').x`
+    CommonJs,
+}
+
+/// Module syntax of `program`, judged by its top-level statements. Files without any evidence
+/// either way, e.g. without imports at all, fall back to the extension of `filename`, which
+/// defaults to ESM as that's what swc parses JSX and TypeScript into.
+pub fn detect_module_syntax(program: &Program, filename: Option<&str>) -> ModuleSyntax {
+    let mut has_commonjs_decl = false;
+    let items = match program {
+        Program::Module(module) => &module.body[..],
+        // Scripts can't have `import` declarations at all
+        Program::Script(_) => return ModuleSyntax::CommonJs,
+    };
+    for item in items {
+        match item {
+            // `import x = require('pkg')` and `export = x` are TypeScript's CommonJS syntax
+            ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(_))
+            | ModuleItem::ModuleDecl(ModuleDecl::TsExportAssignment(_)) => has_commonjs_decl = true,
+            ModuleItem::ModuleDecl(_) => return ModuleSyntax::Esm,
+            ModuleItem::Stmt(_) => {}
+        }
+    }
+
+    let mut finder = CommonJsFinder { found: false };
+    program.visit_with(&mut finder);
+    if has_commonjs_decl || finder.found {
+        return ModuleSyntax::CommonJs;
+    }
+
+    let extension = filename.and_then(|filename| filename.rsplit_once('.'));
+    match extension.map(|(_, extension)| extension) {
+        Some("cjs" | "cts") => ModuleSyntax::CommonJs,
+        _ => ModuleSyntax::Esm,
+    }
+}
+
+/// Looks for `require()` calls and `module.exports` or `exports` outside of functions.
+struct CommonJsFinder {
+    found: bool,
+}
+
+impl Visit for CommonJsFinder {
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        if let Callee::Expr(callee) = &node.callee {
+            if matches!(&**callee, Expr::Ident(ident) if ident.sym == *"require") {
+                self.found = true;
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_member_expr(&mut self, node: &MemberExpr) {
+        match &*node.obj {
+            Expr::Ident(ident) if ident.sym == *"exports" => self.found = true,
+            Expr::Ident(ident) if ident.sym == *"module" => {
+                self.found |= node
+                    .prop
+                    .as_ident()
+                    .is_some_and(|prop| prop.sym == *"exports")
+            }
+            _ => {}
+        }
+        node.visit_children_with(self);
+    }
+}
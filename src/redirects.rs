@@ -0,0 +1,84 @@
+use crate::config::ConfigError;
+use crate::import_analysis::ImportSpecification;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use swc_core::atoms::Atom;
+use swc_core::ecma::ast::Id;
+
+/// An import whose references are injected through a different import, configured as
+/// `"source#export": "target#export"`.
+pub struct Redirect {
+    pub source: Id,
+    /// Local name of the target import, which `_di` receives instead of the source.
+    pub target_local: Atom,
+    pub target_package: Atom,
+    pub target_export: Atom,
+    /// Whether the module already imports the target, otherwise it's added once used.
+    pub is_imported: bool,
+    pub is_used: bool,
+}
+
+/// Match the configured redirects against the module's imports. `bindings` are the module's
+/// other declarations, which added imports must not clash with.
+pub fn resolve_redirects(
+    redirects: &BTreeMap<String, String>,
+    imports: &[Rc<ImportSpecification>],
+    bindings: &[Id],
+) -> Result<Vec<Redirect>, ConfigError> {
+    let mut resolved: Vec<Redirect> = vec![];
+    for import in imports {
+        let key = format!(
+            "{}#{}",
+            import.package_name, import.dependency_imported_symbol
+        );
+        let Some(target) = redirects.get(&key) else {
+            continue;
+        };
+        let Some((target_package, target_export)) = target.rsplit_once('#') else {
+            return Err(ConfigError::InvalidRedirectTarget(target.clone()));
+        };
+
+        let existing = imports.iter().find(|candidate| {
+            candidate.package_name == target_package
+                && candidate.dependency_imported_symbol == target_export
+        });
+        let target_local = match existing {
+            Some(existing) => existing.local_imported_symbol.clone(),
+            None => {
+                // Another redirect may already add the same target
+                let added = resolved.iter().find(|redirect| {
+                    !redirect.is_imported
+                        && redirect.target_package == target_package
+                        && redirect.target_export == target_export
+                });
+                match added {
+                    Some(added) => added.target_local.clone(),
+                    None => unique_local(target_export, imports, bindings),
+                }
+            }
+        };
+        resolved.push(Redirect {
+            source: import.symbol_id.clone(),
+            target_local,
+            target_package: target_package.into(),
+            target_export: target_export.into(),
+            is_imported: existing.is_some(),
+            is_used: false,
+        });
+    }
+    Ok(resolved)
+}
+
+/// `name`, prefixed with underscores until it doesn't clash with an existing import or
+/// declaration.
+fn unique_local(name: &str, imports: &[Rc<ImportSpecification>], bindings: &[Id]) -> Atom {
+    let mut local = name.to_string();
+    while imports
+        .iter()
+        .any(|import| import.local_imported_symbol == *local)
+        || bindings.iter().any(|(sym, _)| *sym == *local)
+    {
+        local.insert(0, '_');
+    }
+    local.into()
+}